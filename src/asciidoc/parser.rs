@@ -1,6 +1,7 @@
 // use crate::nom::markdown::MarkdownInline;
 // use crate::nom::markdown::MarkdownText;
 
+use crate::front_matter;
 use nom::{
     branch::alt,
     bytes::complete::{is_not, tag, take, take_while1},
@@ -11,9 +12,8 @@ use nom::{
     IResult,
 };
 
-// TODO: I would like this to return a list of key/value yaml pairs
 fn parse_front_matter(i: &str) -> IResult<&str, &str> {
-    delimited(tag("---"), is_not("---"), tag("---"))(i)
+    front_matter::yaml(i)
 }
 
 // TODO: Thematic break
@@ -50,11 +50,11 @@ fn parse_front_matter(i: &str) -> IResult<&str, &str> {
 // // A single-line comment.
 
 // TODO: comment block
-////
+// ////
 // A block comment.
 //
 // Notice it's a delimited block.
-////
+// ////
 
 // from nom json example
 // fn key_value<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
@@ -212,7 +212,7 @@ mod tests {
     #[test]
     fn test_parse_front_matter() {
         let string = "---\nAuthor: Sean\n---";
-        assert_eq!(parse_front_matter(string), Ok(("", "\nAuthor: Sean\n")));
+        assert_eq!(parse_front_matter(string), Ok(("", "Author: Sean")));
     }
 
     // #[test]
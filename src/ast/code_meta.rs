@@ -0,0 +1,286 @@
+use super::{Code, Point, Position};
+use crate::markdown::unescape_with_offsets;
+use nom::{
+    branch::alt,
+    bytes::complete::{escaped_transform, is_not, take_while1},
+    character::complete::{char, digit1, space0},
+    combinator::{map, map_res, opt, value},
+    multi::separated_list1,
+    sequence::{delimited, pair, preceded, tuple},
+    IResult,
+};
+use std::error::Error;
+use std::fmt;
+use std::ops::{Range, RangeInclusive};
+
+// The meta of fenced code is free form, this reads the conventions shared by most renderers:
+// ```rust title="main.rs" {1,3-5} showLineNumbers
+
+/// An attribute of the meta of a code block, as returned by [`Code::meta_attributes`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetaAttribute {
+    pub kind: MetaAttributeKind,
+    /// The byte range of the attribute in the meta string, which has escapes and references resolved.
+    pub span: Range<usize>,
+    /// Where the attribute is in the source. Only code blocks read by the markdown parser know where
+    /// their meta is, code read from mdast JSON or made with [`Code::new`] has no position.
+    pub position: Option<Position>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetaAttributeKind {
+    /// `key=value`, `key="value"` or `key='value'`. A quoted value may escape its quote or a backslash
+    /// with a backslash in the meta. The parser has resolved the backslash escapes of the markdown by
+    /// then, so in markdown the backslash is escaped too: `title="say \\\"hi\\\""`.
+    Pair { key: String, value: String },
+    /// A bare word such as `showLineNumbers`.
+    Flag(String),
+    /// Lines to highlight such as `{1,3-5}`, as 1-indexed inclusive ranges in source order.
+    Lines(Vec<RangeInclusive<usize>>),
+}
+
+/// Error returned when the meta of a code block could not be read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeMetaError {
+    message: String,
+    offset: usize,
+}
+
+impl CodeMetaError {
+    fn new(message: impl Into<String>, offset: usize) -> CodeMetaError {
+        CodeMetaError {
+            message: message.into(),
+            offset,
+        }
+    }
+
+    /// A description of what went wrong.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The byte offset in the meta string at which reading failed.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl fmt::Display for CodeMetaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at offset {}", self.message, self.offset)
+    }
+}
+
+impl Error for CodeMetaError {}
+
+impl Code {
+    /// Reads the meta into its attributes, separated by spaces or tabs.
+    /// A code block without meta has no attributes.
+    pub fn meta_attributes(&self) -> Result<Vec<MetaAttribute>, CodeMetaError> {
+        let meta = match &self.meta {
+            Some(meta) => meta,
+            None => return Ok(Vec::new()),
+        };
+        let mut attributes = attributes(meta)?;
+        if let Some(start) = self.meta_start {
+            // spans are mapped back through the escapes and references of the meta as written
+            let raw = self.raw_meta().unwrap_or(meta);
+            let (value, offsets) = unescape_with_offsets(raw);
+            if value == *meta {
+                let point = |offset: usize| {
+                    let units = raw[..offsets[offset]].encode_utf16().count() as u64;
+                    Point::new(
+                        start.line,
+                        start.column + units,
+                        start.offset.map(|offset| offset + units),
+                    )
+                };
+                for attribute in &mut attributes {
+                    attribute.position = Some(Position::new(
+                        point(attribute.span.start),
+                        point(attribute.span.end),
+                        None,
+                    ));
+                }
+            }
+        }
+        Ok(attributes)
+    }
+}
+
+fn is_word(c: char) -> bool {
+    !c.is_whitespace() && !matches!(c, '=' | '{' | '}' | '"' | '\'')
+}
+
+fn quoted(quote: char) -> impl Fn(&str) -> IResult<&str, String> {
+    move |i| {
+        let unescaped = escaped_transform(
+            is_not(if quote == '"' { "\"\\" } else { "'\\" }),
+            '\\',
+            alt((
+                value("\\", char('\\')),
+                value(if quote == '"' { "\"" } else { "'" }, char(quote)),
+            )),
+        );
+        delimited(
+            char(quote),
+            map(opt(unescaped), Option::unwrap_or_default),
+            char(quote),
+        )(i)
+    }
+}
+
+fn line_number(i: &str) -> IResult<&str, usize> {
+    map_res(digit1, |d: &str| d.parse())(i)
+}
+
+fn line_range(i: &str) -> IResult<&str, RangeInclusive<usize>> {
+    map(
+        tuple((
+            line_number,
+            opt(preceded(tuple((space0, char('-'), space0)), line_number)),
+        )),
+        |(start, end)| start..=end.unwrap_or(start),
+    )(i)
+}
+
+fn lines(i: &str) -> IResult<&str, Vec<RangeInclusive<usize>>> {
+    delimited(
+        pair(char('{'), space0),
+        separated_list1(tuple((space0, char(','), space0)), line_range),
+        pair(space0, char('}')),
+    )(i)
+}
+
+fn attribute(i: &str) -> Result<(&str, MetaAttributeKind), &'static str> {
+    if i.starts_with('{') {
+        let (rest, ranges) = lines(i).map_err(|_| "invalid line ranges")?;
+        if ranges
+            .iter()
+            .any(|r| *r.start() == 0 || r.start() > r.end())
+        {
+            return Err("invalid line ranges");
+        }
+        return Ok((rest, MetaAttributeKind::Lines(ranges)));
+    }
+    let (rest, key) =
+        take_while1::<_, _, ()>(is_word)(i).map_err(|_| "expected an attribute name")?;
+    match rest.strip_prefix('=') {
+        Some(rest) => {
+            let (rest, value) = match rest.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    quoted(quote)(rest).map_err(|_| "unterminated quoted value")?
+                }
+                _ => take_while1::<_, _, ()>(|c: char| !c.is_whitespace())(rest)
+                    .map(|(rest, value)| (rest, value.to_string()))
+                    .map_err(|_| "expected a value")?,
+            };
+            Ok((rest, pair_kind(key, value)))
+        }
+        None => Ok((rest, MetaAttributeKind::Flag(key.to_string()))),
+    }
+}
+
+fn pair_kind(key: &str, value: String) -> MetaAttributeKind {
+    MetaAttributeKind::Pair {
+        key: key.to_string(),
+        value,
+    }
+}
+
+fn attributes(meta: &str) -> Result<Vec<MetaAttribute>, CodeMetaError> {
+    let mut attributes = Vec::new();
+    let mut rest = meta.trim_start();
+    while !rest.is_empty() {
+        let start = meta.len() - rest.len();
+        let (after, kind) =
+            attribute(rest).map_err(|message| CodeMetaError::new(message, start))?;
+        let end = meta.len() - after.len();
+        if !after.is_empty() && !after.starts_with(char::is_whitespace) {
+            return Err(CodeMetaError::new(
+                "expected whitespace after attribute",
+                end,
+            ));
+        }
+        attributes.push(MetaAttribute {
+            kind,
+            span: start..end,
+            position: None,
+        });
+        rest = after.trim_start();
+    }
+    Ok(attributes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(meta: &str) -> Vec<MetaAttributeKind> {
+        attributes(meta)
+            .unwrap()
+            .into_iter()
+            .map(|attribute| attribute.kind)
+            .collect()
+    }
+
+    #[test]
+    fn meta_attributes() {
+        let meta = r#"title="main.rs" {1,3-5} showLineNumbers"#;
+        assert_eq!(
+            attributes(meta).unwrap(),
+            vec![
+                MetaAttribute {
+                    kind: pair_kind("title", "main.rs".to_string()),
+                    span: 0..15,
+                    position: None,
+                },
+                MetaAttribute {
+                    kind: MetaAttributeKind::Lines(vec![1..=1, 3..=5]),
+                    span: 16..23,
+                    position: None,
+                },
+                MetaAttribute {
+                    kind: MetaAttributeKind::Flag("showLineNumbers".to_string()),
+                    span: 24..39,
+                    position: None,
+                },
+            ]
+        );
+        assert_eq!(
+            kinds(r#"a='it\'s' b="say \"hi\" \\" c=x=y d="" { 2 - 4 , 7 }"#),
+            vec![
+                pair_kind("a", "it's".to_string()),
+                pair_kind("b", "say \"hi\" \\".to_string()),
+                pair_kind("c", "x=y".to_string()),
+                pair_kind("d", String::new()),
+                MetaAttributeKind::Lines(vec![2..=4, 7..=7]),
+            ]
+        );
+        assert_eq!(kinds("  \t"), vec![]);
+    }
+
+    #[test]
+    fn meta_errors() {
+        let error = |meta: &str| {
+            let error = attributes(meta).unwrap_err();
+            (error.message().to_string(), error.offset())
+        };
+        assert_eq!(
+            error("a title=\"x"),
+            ("unterminated quoted value".to_string(), 2)
+        );
+        assert_eq!(error("title="), ("expected a value".to_string(), 0));
+        assert_eq!(error("{1,}"), ("invalid line ranges".to_string(), 0));
+        assert_eq!(error("x {5-3}"), ("invalid line ranges".to_string(), 2));
+        assert_eq!(error("{0}"), ("invalid line ranges".to_string(), 0));
+        assert_eq!(
+            error("{1}x"),
+            ("expected whitespace after attribute".to_string(), 3)
+        );
+        assert_eq!(
+            error("\"x\""),
+            ("expected an attribute name".to_string(), 0)
+        );
+    }
+}
@@ -0,0 +1,276 @@
+use super::{
+    Association, Content, FlowContent, FootnoteDefinition, FootnoteReference, ListContent,
+    PhrasingContent, Point, Position, Root, RowContent, StaticPhrasingContent, TableContent, Text,
+};
+use crate::markdown::footnote_label;
+use std::ops::Range;
+
+/// How the footnote references of a document relate to its footnote definitions, as returned by
+/// [`Root::footnotes`]. Everything is listed in source order.
+#[derive(Debug)]
+pub struct FootnoteReport<'a> {
+    /// Every footnote definition, including those nested in other blocks.
+    pub definitions: Vec<&'a FootnoteDefinition>,
+    /// Every footnote reference, including those in the definitions and in inline footnotes.
+    pub references: Vec<&'a FootnoteReference>,
+    /// References without a definition. The markdown parser leaves those as text, like GFM does, so
+    /// they are also looked for in text nodes. Those found in text only have a position when they are on
+    /// the first or last line of their text node.
+    pub orphaned: Vec<FootnoteReference>,
+    /// Definitions no reference resolves to, which includes all but the first definition of an identifier.
+    pub unused: Vec<&'a FootnoteDefinition>,
+}
+
+// A footnote reference, or a footnote label left as text.
+enum Call<'a> {
+    Reference(&'a FootnoteReference),
+    Text(FootnoteReference),
+}
+
+#[derive(Default)]
+struct Footnotes<'a> {
+    definitions: Vec<&'a FootnoteDefinition>,
+    calls: Vec<Call<'a>>,
+}
+
+impl<'a> Footnotes<'a> {
+    fn flow(&mut self, content: &'a FlowContent) {
+        match content {
+            FlowContent::BlockQuote(b) => b.children.iter().for_each(|child| self.flow(child)),
+            FlowContent::List(l) => {
+                for ListContent::ListItem(item) in &l.children {
+                    item.children.iter().for_each(|child| self.flow(child));
+                }
+            }
+            FlowContent::FootnoteDefinition(f) => {
+                self.definitions.push(f);
+                f.children.iter().for_each(|child| self.flow(child));
+            }
+            FlowContent::Content(Content::Paragraph(p)) => self.phrasing(&p.children),
+            FlowContent::Heading(h) => self.phrasing(&h.children),
+            FlowContent::Table(t) => {
+                for TableContent::TableRow(row) in &t.children {
+                    for RowContent::TableCell(cell) in &row.children {
+                        self.phrasing(&cell.children);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn phrasing(&mut self, children: &'a [PhrasingContent]) {
+        for child in children {
+            match child {
+                PhrasingContent::Link(l) => l.children.iter().for_each(|c| self.static_phrasing(c)),
+                PhrasingContent::LinkReference(l) => {
+                    l.children.iter().for_each(|c| self.static_phrasing(c))
+                }
+                PhrasingContent::StaticPhrasingContent(c) => self.static_phrasing(c),
+            }
+        }
+    }
+
+    fn static_phrasing(&mut self, content: &'a StaticPhrasingContent) {
+        match content {
+            StaticPhrasingContent::Emphasis(e) => self.phrasing(&e.children),
+            StaticPhrasingContent::Strong(s) => self.phrasing(&s.children),
+            StaticPhrasingContent::Delete(d) => self.phrasing(&d.children),
+            StaticPhrasingContent::Footnote(f) => self.phrasing(&f.children),
+            StaticPhrasingContent::FootnoteReference(r) => self.calls.push(Call::Reference(r)),
+            StaticPhrasingContent::Text(t) => self.text(t),
+            _ => {}
+        }
+    }
+
+    // Looks for `[^label]` in the text as written, where backslash escapes can still be told apart.
+    fn text(&mut self, text: &Text) {
+        let value = match &text.value {
+            Some(value) => String::from_utf8_lossy(value),
+            None => return,
+        };
+        let source = text.raw.as_deref().unwrap_or(&value);
+        let mut from = 0;
+        while let Some(found) = source[from..].find("[^") {
+            let start = from + found;
+            from = start + 1;
+            let backslashes = source[..start].len() - source[..start].trim_end_matches('\\').len();
+            if backslashes % 2 == 1 {
+                continue;
+            }
+            if let Ok((rest, label)) = footnote_label(&source[start..]) {
+                let end = source.len() - rest.len();
+                let position = text
+                    .position
+                    .and_then(|position| text_position(position, source, start..end));
+                self.calls.push(Call::Text(FootnoteReference {
+                    association: Association::new(label),
+                    position,
+                }));
+                from = end;
+            }
+        }
+    }
+}
+
+fn units(text: &str) -> u64 {
+    text.encode_utf16().count() as u64
+}
+
+// Where `range` of the text is in the source. That is only known counting from the start of the text on
+// its first line and back from its end on its last line, as the lines in between may have been indented.
+fn text_position(position: Position, source: &str, range: Range<usize>) -> Option<Position> {
+    let forward = |from: Point, units: u64| {
+        Point::new(
+            from.line,
+            from.column + units,
+            from.offset.map(|o| o + units),
+        )
+    };
+    let back = |from: Point, units: u64| {
+        Point::new(
+            from.line,
+            from.column.saturating_sub(units),
+            from.offset.map(|o| o.saturating_sub(units)),
+        )
+    };
+    let label = units(&source[range.clone()]);
+    if !source[..range.end].contains('\n') {
+        let start = forward(position.start, units(&source[..range.start]));
+        Some(Position::new(start, forward(start, label), None))
+    } else if !source[range.start..].contains('\n') {
+        let end = back(position.end, units(&source[range.end..]));
+        Some(Position::new(back(end, label), end, None))
+    } else {
+        None
+    }
+}
+
+impl Root {
+    fn collect_footnotes(&self) -> Footnotes<'_> {
+        let mut footnotes = Footnotes::default();
+        self.flow_content()
+            .for_each(|content| footnotes.flow(content));
+        footnotes
+    }
+
+    /// Resolves the association of a FootnoteReference to its FootnoteDefinition.
+    /// When several definitions share an identifier the first one wins.
+    pub fn resolve_footnote(&self, association: &Association) -> Option<&FootnoteDefinition> {
+        self.collect_footnotes()
+            .definitions
+            .into_iter()
+            .find(|d| d.association.identifier == association.identifier)
+    }
+
+    /// Finds the footnote references without a definition and the definitions that are never referenced.
+    pub fn footnotes(&self) -> FootnoteReport<'_> {
+        let Footnotes { definitions, calls } = self.collect_footnotes();
+        let resolve = |association: &Association| {
+            definitions
+                .iter()
+                .position(|d| d.association.identifier == association.identifier)
+        };
+
+        let mut used = vec![false; definitions.len()];
+        let mut references = Vec::new();
+        let mut orphaned = Vec::new();
+        for call in calls {
+            match call {
+                Call::Reference(reference) => {
+                    references.push(reference);
+                    match resolve(&reference.association) {
+                        Some(index) => used[index] = true,
+                        None => orphaned.push(reference.clone()),
+                    }
+                }
+                // text that names a defined footnote was not meant as a reference
+                Call::Text(reference) => {
+                    if resolve(&reference.association).is_none() {
+                        orphaned.push(reference);
+                    }
+                }
+            }
+        }
+        let unused = definitions
+            .iter()
+            .zip(used)
+            .filter(|(_, used)| !used)
+            .map(|(&definition, _)| definition)
+            .collect();
+        FootnoteReport {
+            definitions,
+            references,
+            orphaned,
+            unused,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::markdown::parse;
+
+    fn identifiers<'a>(associations: impl Iterator<Item = &'a super::Association>) -> Vec<&'a str> {
+        associations
+            .map(|association| association.identifier.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn report() {
+        let root = parse(
+            "A[^1] *b[^Missing]* ^[c[^2]]\n\n> [^1]: one\n\n[^2]: two[^3]\n\n[^3]: three\n[^unused]: four\n[^1]: again\n",
+        )
+        .unwrap();
+        let report = root.footnotes();
+        assert_eq!(
+            identifiers(report.references.iter().map(|r| &r.association)),
+            vec!["1", "2", "3"]
+        );
+        // the undefined reference is text in the tree but still reported
+        assert_eq!(
+            identifiers(report.orphaned.iter().map(|r| &r.association)),
+            vec!["missing"]
+        );
+        assert_eq!(
+            identifiers(report.unused.iter().map(|d| &d.association)),
+            vec!["unused", "1"]
+        );
+        assert_eq!(report.definitions.len(), 5);
+
+        let reference = &report.references[0];
+        let definition = root.resolve_footnote(&reference.association).unwrap();
+        assert_eq!(definition.position.unwrap().start.line, 3);
+    }
+
+    #[test]
+    fn orphaned_in_text() {
+        let root =
+            parse("[^a] [^1] \\[^esc] [^b]\nc [^c] d\ne [^e]\n> é [^d]\n\n[^1]: one\n").unwrap();
+        let report = root.footnotes();
+        assert_eq!(
+            identifiers(report.orphaned.iter().map(|r| &r.association)),
+            vec!["a", "b", "c", "e", "d"]
+        );
+        let places: Vec<_> = report
+            .orphaned
+            .iter()
+            .map(|r| {
+                r.position
+                    .map(|p| (p.start.line, p.start.column, p.end.column))
+            })
+            .collect();
+        // c is on a line in the middle of its text
+        assert_eq!(
+            places,
+            vec![
+                Some((1, 1, 5)),
+                Some((1, 19, 23)),
+                None,
+                Some((3, 3, 7)),
+                Some((4, 5, 9))
+            ]
+        );
+    }
+}
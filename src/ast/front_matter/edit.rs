@@ -0,0 +1,729 @@
+use super::{FrontMatterError, NOT_A_MAP};
+use crate::ast::{FrontmatterContent, Json, Toml, YAML};
+use crate::front_matter;
+use serde_json::value::RawValue;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::str::Chars;
+use yaml_rust2::parser::{Event, Parser};
+use yaml_rust2::scanner::{Marker, TScalarStyle};
+use yaml_rust2::{Yaml, YamlLoader};
+
+// Edits change the text of the front matter in place instead of writing out its values again,
+// so only the key being edited looks different afterwards.
+
+// the line ending of the first line
+fn line_ending(text: &str) -> &'static str {
+    match text.find('\n') {
+        Some(end) if text[..end].ends_with('\r') => "\r\n",
+        _ => "\n",
+    }
+}
+
+// the line ending of a value, which has no line ending to go by when it is a single line
+fn value_line_ending(value: &str) -> &'static str {
+    if value.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    }
+}
+
+fn splice(source: &str, range: Range<usize>, text: &str) -> String {
+    format!("{}{}{}", &source[..range.start], text, &source[range.end..])
+}
+
+impl FrontmatterContent {
+    /// Sets the value of `key`, in its place when the key exists and after the last key otherwise.
+    /// Comments, key order and quoting of everything else in the front matter are kept.
+    pub fn set(&mut self, key: &str, value: impl Into<Value>) -> Result<(), FrontMatterError> {
+        let value = value.into();
+        match self {
+            FrontmatterContent::YAML(yaml) => yaml.set(key, &value),
+            FrontmatterContent::Toml(toml) => toml.set(key, &value),
+            FrontmatterContent::Json(json) => json.set(key, &value),
+        }
+    }
+
+    /// Adds `key` after the last key unless it exists already, and returns whether it was added.
+    pub fn insert(&mut self, key: &str, value: impl Into<Value>) -> Result<bool, FrontMatterError> {
+        if self.map()?.contains_key(key) {
+            return Ok(false);
+        }
+        self.set(key, value)?;
+        Ok(true)
+    }
+
+    /// Removes `key` and its value, and returns whether it existed.
+    pub fn remove(&mut self, key: &str) -> Result<bool, FrontMatterError> {
+        match self {
+            FrontmatterContent::YAML(yaml) => yaml.remove(key),
+            FrontmatterContent::Toml(toml) => toml.remove(key),
+            FrontmatterContent::Json(json) => json.remove(key),
+        }
+    }
+
+    /// Returns `document` with its front matter replaced by this node and everything after it unchanged.
+    /// Front matter in the same format keeps its fences. Otherwise the node is written with fences of its
+    /// own in place of the front matter, or in front of the document when it has none.
+    pub fn replace_in(&self, document: &str) -> String {
+        let (fence, value, same_format) = match self {
+            FrontmatterContent::YAML(yaml) => ("---", &yaml.value, front_matter::yaml(document)),
+            FrontmatterContent::Toml(toml) => ("+++", &toml.value, front_matter::toml(document)),
+            // the braces are part of the value
+            FrontmatterContent::Json(json) => ("", &json.value, front_matter::json(document)),
+        };
+        let line_ending = line_ending(document);
+        let value = String::from_utf8_lossy(value);
+        // lines added to a value that had a single line end like the lines of the document
+        let value = match value_line_ending(&value) {
+            "\n" if line_ending == "\r\n" => value.replace('\n', line_ending).into(),
+            _ => value,
+        };
+
+        if let Ok((_, old)) = same_format {
+            let start = old.as_ptr() as usize - document.as_ptr() as usize;
+            let mut end = start + old.len();
+            // empty front matter has no line of its own
+            let value = match (old.is_empty(), value.is_empty()) {
+                (true, false) => format!("{}{}", value, line_ending),
+                (false, true) => {
+                    end += document[end..].find('\n').map_or(0, |newline| newline + 1);
+                    String::new()
+                }
+                _ => value.into_owned(),
+            };
+            return splice(document, start..end, &value);
+        }
+
+        let fenced = match (fence, value.is_empty()) {
+            ("", _) => value.into_owned(),
+            (fence, true) => format!("{}{}{}", fence, line_ending, fence),
+            (fence, false) => format!("{0}{1}{2}{1}{0}", fence, line_ending, value),
+        };
+        let body = front_matter::yaml(document)
+            .or_else(|_| front_matter::toml(document))
+            .or_else(|_| front_matter::json(document));
+        match body {
+            Ok((body, _)) => format!("{}{}", fenced, body),
+            Err(_) => format!("{}{}{}", fenced, line_ending, document),
+        }
+    }
+}
+
+// A key of the mapping at the top of YAML front matter.
+struct YamlEntry {
+    // None for keys that are not scalars
+    key: Option<String>,
+    // the lines of the key and its value, from the start of the key
+    lines: Range<usize>,
+    column: usize,
+    // the style of a scalar value and the line and column it starts at
+    scalar: Option<(TScalarStyle, usize, usize)>,
+}
+
+impl YAML {
+    fn next_event(&self, parser: &mut Parser<Chars>) -> Result<(Event, Marker), FrontMatterError> {
+        parser.next_token().map_err(|error| self.scan_error(&error))
+    }
+
+    // consumes the rest of a sequence or mapping that starts with `event`
+    fn skip(&self, parser: &mut Parser<Chars>, event: Event) -> Result<(), FrontMatterError> {
+        let mut depth = match event {
+            Event::SequenceStart(..) | Event::MappingStart(..) => 1,
+            _ => 0,
+        };
+        while depth > 0 {
+            match self.next_event(parser)?.0 {
+                Event::SequenceStart(..) | Event::MappingStart(..) => depth += 1,
+                Event::SequenceEnd | Event::MappingEnd => depth -= 1,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    // Finds the keys of the mapping in `source` by the lines they start on. A key and its value span the
+    // lines up to the next key, except for blank lines and comments at the end which are not indented.
+    fn entries(&self, source: &str) -> Result<Vec<YamlEntry>, FrontMatterError> {
+        let mut parser = Parser::new_from_str(source);
+        let (top, marker) = loop {
+            match self.next_event(&mut parser)? {
+                (Event::StreamStart, _) | (Event::DocumentStart, _) => continue,
+                event => break event,
+            }
+        };
+        match top {
+            // nothing but whitespace or comments
+            Event::StreamEnd => return Ok(Vec::new()),
+            Event::Scalar(value, TScalarStyle::Plain, ..)
+                if matches!(value.as_str(), "" | "~" | "null") =>
+            {
+                return Ok(Vec::new())
+            }
+            Event::MappingStart(..) if source.chars().nth(marker.index()) != Some('{') => {}
+            Event::MappingStart(..) => {
+                return Err(self.error("only front matter with a key on each line can be edited"))
+            }
+            _ => return Err(self.error(NOT_A_MAP)),
+        }
+
+        let mut keys = Vec::new();
+        loop {
+            let (event, marker) = self.next_event(&mut parser)?;
+            let key = match event {
+                Event::MappingEnd => break,
+                Event::Scalar(key, ..) => Some(key),
+                event => {
+                    self.skip(&mut parser, event)?;
+                    None
+                }
+            };
+            let scalar = match self.next_event(&mut parser)? {
+                (Event::Scalar(_, style, ..), value) => {
+                    Some((style, value.line() - 1, value.col()))
+                }
+                (event, _) => {
+                    self.skip(&mut parser, event)?;
+                    None
+                }
+            };
+            keys.push((key, marker.line() - 1, marker.col(), scalar));
+        }
+
+        let lines: Vec<&str> = source.split_inclusive('\n').collect();
+        let mut entries: Vec<YamlEntry> = Vec::new();
+        for (index, (key, line, column, scalar)) in keys.iter().cloned().enumerate() {
+            let mut end = keys.get(index + 1).map_or(lines.len(), |next| next.1);
+            if end == line {
+                return Err(self.error("only front matter with a key on each line can be edited"));
+            }
+            while end > line + 1 {
+                let text = lines[end - 1].trim_start();
+                let indent = lines[end - 1].len() - text.len();
+                if !(text.trim_end().is_empty() || (text.starts_with('#') && indent <= column)) {
+                    break;
+                }
+                end -= 1;
+            }
+            entries.push(YamlEntry {
+                key,
+                lines: line..end,
+                column,
+                scalar,
+            });
+        }
+        Ok(entries)
+    }
+
+    fn set(&mut self, key: &str, value: &Value) -> Result<(), FrontMatterError> {
+        let source = String::from_utf8_lossy(&self.value).into_owned();
+        let entries = self.entries(&source)?;
+        let line_ending = value_line_ending(&source);
+        let edited = match entries
+            .iter()
+            .find(|entry| entry.key.as_deref() == Some(key))
+        {
+            Some(entry) => {
+                let range = byte_range(&source, &entry.lines);
+                let (style, comment) = match entry.scalar {
+                    Some((style, line, column)) if entry.lines == (line..line + 1) => (
+                        Some(style),
+                        yaml_comment(&source[range.clone()], column, style),
+                    ),
+                    Some((style, ..)) => (Some(style), ""),
+                    None => (None, ""),
+                };
+                let mut text = format!(
+                    "{}{}: {}{}",
+                    " ".repeat(entry.column),
+                    yaml_string(key, None, false),
+                    yaml_node(value, style, false),
+                    comment
+                );
+                if source[range.clone()].ends_with('\n') {
+                    text.push_str(line_ending);
+                }
+                splice(&source, range, &text)
+            }
+            None => {
+                let column = entries.first().map_or(0, |entry| entry.column);
+                let mut edited = source.clone();
+                if !edited.is_empty() && !edited.ends_with('\n') {
+                    edited.push_str(line_ending);
+                }
+                edited.push_str(&format!(
+                    "{}{}: {}",
+                    " ".repeat(column),
+                    yaml_string(key, None, false),
+                    yaml_node(value, None, false)
+                ));
+                edited
+            }
+        };
+        self.value = edited.into_bytes();
+        Ok(())
+    }
+
+    fn remove(&mut self, key: &str) -> Result<bool, FrontMatterError> {
+        let source = String::from_utf8_lossy(&self.value).into_owned();
+        let entries = self.entries(&source)?;
+        let range = match entries
+            .iter()
+            .find(|entry| entry.key.as_deref() == Some(key))
+        {
+            Some(entry) => byte_range(&source, &entry.lines),
+            None => return Ok(false),
+        };
+        let mut before = &source[..range.start];
+        // the last line has no line ending to remove along with it
+        if range.end == source.len() && !source.ends_with('\n') {
+            before = before.strip_suffix('\n').unwrap_or(before);
+            before = before.strip_suffix('\r').unwrap_or(before);
+        }
+        self.value = format!("{}{}", before, &source[range.end..]).into_bytes();
+        Ok(true)
+    }
+}
+
+// the bytes of `lines` of `source`, line endings included
+fn byte_range(source: &str, lines: &Range<usize>) -> Range<usize> {
+    let mut offsets = source
+        .split_inclusive('\n')
+        .scan(0, |offset, line| {
+            *offset += line.len();
+            Some(*offset)
+        })
+        .collect::<Vec<_>>();
+    offsets.insert(0, 0);
+    offsets[lines.start]..offsets[lines.end]
+}
+
+// The comment after a scalar that starts at `column` of `line`, along with the whitespace before it.
+fn yaml_comment(line: &str, column: usize, style: TScalarStyle) -> &str {
+    let line = line.trim_end_matches(['\r', '\n']);
+    let start = line
+        .char_indices()
+        .nth(column)
+        .map_or(line.len(), |(start, _)| start);
+    let scalar = &line.as_bytes()[start..];
+    let mut end = match style {
+        TScalarStyle::Plain => 0,
+        TScalarStyle::SingleQuoted | TScalarStyle::DoubleQuoted => {
+            let quote = scalar[0];
+            let mut index = 1;
+            while index < scalar.len() {
+                match scalar[index] {
+                    b'\\' if quote == b'"' => index += 1,
+                    b'\'' if quote == b'\'' && scalar.get(index + 1) == Some(&b'\'') => index += 1,
+                    byte if byte == quote => break,
+                    _ => {}
+                }
+                index += 1;
+            }
+            index + 1
+        }
+        _ => return "",
+    };
+    // a comment is a `#` after whitespace
+    while end < scalar.len() {
+        if scalar[end] == b'#' && end > 0 && (scalar[end - 1] == b' ' || scalar[end - 1] == b'\t') {
+            let rest = &line[start..start + end];
+            return &line[start + rest.trim_end().len()..];
+        }
+        end += 1;
+    }
+    ""
+}
+
+// Writes a value on one line, in flow style for lists and maps.
+fn yaml_node(value: &Value, style: Option<TScalarStyle>, flow: bool) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Bool(value) => value.to_string(),
+        Value::Number(value) => value.to_string(),
+        Value::String(value) => yaml_string(value, style, flow),
+        Value::Array(values) => {
+            let values: Vec<String> = values
+                .iter()
+                .map(|value| yaml_node(value, None, true))
+                .collect();
+            format!("[{}]", values.join(", "))
+        }
+        Value::Object(map) => {
+            let entries: Vec<String> = map
+                .iter()
+                .map(|(key, value)| {
+                    format!(
+                        "{}: {}",
+                        yaml_string(key, None, true),
+                        yaml_node(value, None, true)
+                    )
+                })
+                .collect();
+            format!("{{{}}}", entries.join(", "))
+        }
+    }
+}
+
+// Strings keep the quotes of the value they replace and are otherwise plain when that reads back as the
+// same string, such as `draft` but not `true` or `a: b`.
+fn yaml_string(value: &str, style: Option<TScalarStyle>, flow: bool) -> String {
+    let plain = || {
+        !(flow && value.contains(|c| ",[]{}".contains(c)))
+            && matches!(YamlLoader::load_from_str(value).as_deref(), Ok([Yaml::String(plain)]) if plain == value)
+    };
+    match style {
+        Some(TScalarStyle::SingleQuoted) if !value.contains(char::is_control) => {
+            format!("'{}'", value.replace('\'', "''"))
+        }
+        Some(TScalarStyle::DoubleQuoted) => Value::from(value).to_string(),
+        _ if plain() => value.to_string(),
+        // JSON strings are double quoted YAML strings
+        _ => Value::from(value).to_string(),
+    }
+}
+
+fn toml_edit_value(value: &Value) -> Result<toml_edit::Value, &'static str> {
+    Ok(match value {
+        Value::Null => return Err("TOML has no null value"),
+        Value::Bool(value) => (*value).into(),
+        Value::Number(value) => match (value.as_i64(), value.as_f64()) {
+            (Some(value), _) => value.into(),
+            (None, Some(value)) => value.into(),
+            (None, None) => return Err("number is out of range for TOML"),
+        },
+        Value::String(value) => value.as_str().into(),
+        Value::Array(values) => values
+            .iter()
+            .map(toml_edit_value)
+            .collect::<Result<toml_edit::Array, _>>()?
+            .into(),
+        Value::Object(map) => map
+            .iter()
+            .map(|(key, value)| Ok((key.as_str(), toml_edit_value(value)?)))
+            .collect::<Result<toml_edit::InlineTable, _>>()?
+            .into(),
+    })
+}
+
+impl Toml {
+    // toml_edit ends the document with a line ending, which the value only has when it ends in a blank line
+    fn write(&self, document: &toml_edit::DocumentMut) -> Vec<u8> {
+        let mut text = document.to_string();
+        if !self.value.ends_with(b"\n") {
+            let end = text.trim_end_matches(['\r', '\n']).len();
+            text.truncate(end);
+        }
+        text.into_bytes()
+    }
+
+    fn set(&mut self, key: &str, value: &Value) -> Result<(), FrontMatterError> {
+        let mut document = self.document()?;
+        let mut value = toml_edit_value(value)
+            .map_err(|message| FrontMatterError::new(message, self.position, 1, 1, 1))?;
+        match document.get_mut(key) {
+            Some(toml_edit::Item::Value(old)) => {
+                // the spacing and any comment after the value stay
+                *value.decor_mut() = old.decor().clone();
+                *old = value;
+            }
+            _ => {
+                document.insert(key, toml_edit::Item::Value(value));
+            }
+        }
+        self.value = self.write(&document);
+        Ok(())
+    }
+
+    fn remove(&mut self, key: &str) -> Result<bool, FrontMatterError> {
+        let mut document = self.document()?;
+        if document.remove(key).is_none() {
+            return Ok(false);
+        }
+        self.value = self.write(&document);
+        Ok(true)
+    }
+}
+
+impl Json {
+    // The keys of the object in the order they are written, with the bytes of their values in `source`.
+    fn entries(&self, source: &str) -> Result<Vec<(String, Range<usize>)>, FrontMatterError> {
+        let values: BTreeMap<String, &RawValue> =
+            serde_json::from_str(source).map_err(|error| self.syntax_error(&error))?;
+        let mut entries: Vec<(String, Range<usize>)> = values
+            .into_iter()
+            .map(|(key, value)| {
+                let start = value.get().as_ptr() as usize - source.as_ptr() as usize;
+                (key, start..start + value.get().len())
+            })
+            .collect();
+        entries.sort_by_key(|(_, value)| value.start);
+        Ok(entries)
+    }
+
+    // where the key at `index` starts, after the opening brace or the comma after the previous value
+    fn key_start(source: &str, entries: &[(String, Range<usize>)], index: usize) -> usize {
+        let after = match index {
+            0 => source.find('{').map_or(0, |brace| brace + 1),
+            _ => {
+                let end = entries[index - 1].1.end;
+                end + source[end..].find(',').map_or(0, |comma| comma + 1)
+            }
+        };
+        source.len() - source[after..].trim_start().len()
+    }
+
+    fn set(&mut self, key: &str, value: &Value) -> Result<(), FrontMatterError> {
+        let source = String::from_utf8_lossy(&self.value).into_owned();
+        let entries = self.entries(&source)?;
+        let edited = match entries.iter().find(|(existing, _)| existing == key) {
+            Some((_, range)) => splice(&source, range.clone(), &value.to_string()),
+            None => {
+                let entry = format!("{}: {}", Value::from(key), value);
+                match entries.last() {
+                    // on a line of its own when the other keys are
+                    Some((_, last)) => {
+                        let brace = source.find('{').map_or(0, |brace| brace + 1);
+                        let separator = &source[brace..Json::key_start(&source, &entries, 0)];
+                        let entry = format!(",{}{}", separator, entry);
+                        splice(&source, last.end..last.end, &entry)
+                    }
+                    None => {
+                        let brace = source.find('{').map_or(0, |brace| brace + 1);
+                        let entry = format!("{}  {}", line_ending(&source), entry);
+                        splice(&source, brace..brace, &entry)
+                    }
+                }
+            }
+        };
+        self.value = edited.into_bytes();
+        Ok(())
+    }
+
+    fn remove(&mut self, key: &str) -> Result<bool, FrontMatterError> {
+        let source = String::from_utf8_lossy(&self.value).into_owned();
+        let entries = self.entries(&source)?;
+        let index = match entries.iter().position(|(existing, _)| existing == key) {
+            Some(index) => index,
+            None => return Ok(false),
+        };
+        let range = if index + 1 < entries.len() {
+            // the next key takes the place of the removed one
+            Json::key_start(&source, &entries, index)..Json::key_start(&source, &entries, index + 1)
+        } else if index > 0 {
+            // along with the comma after the previous value
+            entries[index - 1].1.end..entries[index].1.end
+        } else {
+            let brace = source.find('{').map_or(0, |brace| brace + 1);
+            brace..entries[index].1.end
+        };
+        self.value = splice(&source, range, "").into_bytes();
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn yaml(value: &str) -> FrontmatterContent {
+        FrontmatterContent::YAML(YAML {
+            value: value.as_bytes().to_vec(),
+            position: None,
+        })
+    }
+
+    fn toml(value: &str) -> FrontmatterContent {
+        FrontmatterContent::Toml(Toml {
+            value: value.as_bytes().to_vec(),
+            position: None,
+        })
+    }
+
+    fn json(value: &str) -> FrontmatterContent {
+        FrontmatterContent::Json(Json {
+            value: value.as_bytes().to_vec(),
+            position: None,
+        })
+    }
+
+    fn value(front_matter: &FrontmatterContent) -> String {
+        let value = match front_matter {
+            FrontmatterContent::YAML(yaml) => &yaml.value,
+            FrontmatterContent::Toml(toml) => &toml.value,
+            FrontmatterContent::Json(json) => &json.value,
+        };
+        String::from_utf8(value.clone()).unwrap()
+    }
+
+    const YAML: &str = "# an ADR\ntitle: 'Use marcup'  # short\nstatus: \"draft\"\ntags:\n- a\n- b\n\n# who decided\ndeciders:\n  - Sean\nupdated: 2021-01-02";
+
+    #[test]
+    fn yaml_set() {
+        let mut front_matter = yaml(YAML);
+        front_matter.set("status", "accepted").unwrap();
+        front_matter.set("title", "Don't use marcup").unwrap();
+        front_matter
+            .set("tags", json!(["c", "d e", "f,g"]))
+            .unwrap();
+        front_matter.set("updated", "2021-02-03").unwrap();
+        assert_eq!(
+            value(&front_matter),
+            "# an ADR\ntitle: 'Don''t use marcup'  # short\nstatus: \"accepted\"\ntags: [c, d e, \"f,g\"]\n\n# who decided\ndeciders:\n  - Sean\nupdated: 2021-02-03"
+        );
+        assert_eq!(
+            front_matter.map().unwrap()["tags"],
+            json!(["c", "d e", "f,g"])
+        );
+
+        let mut front_matter = yaml("a: 1\r\nb: 2");
+        front_matter.set("a", json!({"c": true})).unwrap();
+        front_matter.set("d", "true").unwrap();
+        front_matter.set("e", "").unwrap();
+        assert_eq!(
+            value(&front_matter),
+            "a: {c: true}\r\nb: 2\r\nd: \"true\"\r\ne: \"\""
+        );
+
+        let mut front_matter = yaml("# nothing yet");
+        front_matter.set("a", 1).unwrap();
+        assert_eq!(value(&front_matter), "# nothing yet\na: 1");
+    }
+
+    #[test]
+    fn yaml_insert_and_remove() {
+        let mut front_matter = yaml(YAML);
+        assert!(!front_matter.insert("status", "accepted").unwrap());
+        assert!(front_matter.remove("tags").unwrap());
+        assert!(front_matter.remove("updated").unwrap());
+        assert!(!front_matter.remove("missing").unwrap());
+        assert!(front_matter.insert("superseded", true).unwrap());
+        assert_eq!(
+            value(&front_matter),
+            "# an ADR\ntitle: 'Use marcup'  # short\nstatus: \"draft\"\n\n# who decided\ndeciders:\n  - Sean\nsuperseded: true"
+        );
+        assert!(front_matter.remove("deciders").unwrap());
+        assert_eq!(
+            value(&front_matter),
+            "# an ADR\ntitle: 'Use marcup'  # short\nstatus: \"draft\"\n\n# who decided\nsuperseded: true"
+        );
+    }
+
+    #[test]
+    fn yaml_errors() {
+        let error = yaml("{a: 1}").set("b", 2).unwrap_err();
+        assert_eq!(
+            error.message(),
+            "only front matter with a key on each line can be edited"
+        );
+        assert!(yaml("- a").remove("a").is_err());
+        assert_eq!(yaml("a: [").set("a", 1).unwrap_err().line(), 2);
+    }
+
+    #[test]
+    fn toml_edits() {
+        let source = "# an ADR\ntitle = \"Use marcup\" # short\nupdated = 2021-01-02\n\n[author]\nname = 'Sean'";
+        let mut front_matter = toml(source);
+        front_matter.set("title", "Don't use marcup").unwrap();
+        assert!(front_matter.insert("tags", json!(["a", 1])).unwrap());
+        assert!(front_matter.remove("updated").unwrap());
+        assert_eq!(
+            value(&front_matter),
+            "# an ADR\ntitle = \"Don't use marcup\" # short\ntags = [\"a\", 1]\n\n[author]\nname = 'Sean'"
+        );
+
+        let error = toml(source).set("title", json!(null)).unwrap_err();
+        assert_eq!(error.message(), "TOML has no null value");
+    }
+
+    #[test]
+    fn json_edits() {
+        let source = "{\n  \"title\": \"Use marcup\",\n  \"tags\": [\"a\"],\n  \"draft\": true\n}";
+        let mut front_matter = json(source);
+        front_matter.set("tags", json!(["b", "c"])).unwrap();
+        assert!(front_matter.insert("status", "accepted").unwrap());
+        assert_eq!(
+            value(&front_matter),
+            "{\n  \"title\": \"Use marcup\",\n  \"tags\": [\"b\",\"c\"],\n  \"draft\": true,\n  \"status\": \"accepted\"\n}"
+        );
+
+        let mut front_matter = json(source);
+        assert!(front_matter.remove("title").unwrap());
+        assert!(front_matter.remove("draft").unwrap());
+        assert_eq!(value(&front_matter), "{\n  \"tags\": [\"a\"]\n}");
+        assert!(front_matter.remove("tags").unwrap());
+        assert_eq!(value(&front_matter), "{\n}");
+        front_matter.set("title", "Use marcup").unwrap();
+        assert_eq!(value(&front_matter), "{\n  \"title\": \"Use marcup\"\n}");
+    }
+
+    #[test]
+    fn replace_in() {
+        let document = "---\ntitle: a\n---\n# Body\r\n\ntext  \n";
+        assert_eq!(
+            yaml("title: b").replace_in(document),
+            "---\ntitle: b\n---\n# Body\r\n\ntext  \n"
+        );
+        assert_eq!(
+            yaml("").replace_in(document),
+            "---\n---\n# Body\r\n\ntext  \n"
+        );
+        assert_eq!(
+            yaml("a: 1").replace_in("---\n---\nbody"),
+            "---\na: 1\n---\nbody"
+        );
+        assert_eq!(
+            yaml("a: 1").replace_in("---\r\n---  \r\nbody"),
+            "---\r\na: 1\r\n---  \r\nbody"
+        );
+        // front matter in another format or none at all
+        assert_eq!(
+            yaml("a: 1").replace_in("+++\na = 1\n+++\nbody"),
+            "---\na: 1\n---\nbody"
+        );
+        assert_eq!(yaml("a: 1").replace_in("body"), "---\na: 1\n---\nbody");
+        assert_eq!(json("{\n}").replace_in("\nbody"), "{\n}\n\nbody");
+    }
+
+    // edits the front matter of `document` as parsed and writes it back
+    fn edit_parsed(document: &str, edit: impl FnOnce(&mut FrontmatterContent)) -> String {
+        let mut root = crate::markdown::parse(document).unwrap();
+        let front_matter = root.front_matter_mut().unwrap();
+        edit(front_matter);
+        front_matter.replace_in(document)
+    }
+
+    #[test]
+    fn edit_parsed_front_matter() {
+        assert_eq!(
+            edit_parsed("+++\ntitle = \"a\"\n+++\nBody\n", |front_matter| {
+                front_matter.set("title", "b").unwrap()
+            }),
+            "+++\ntitle = \"b\"\n+++\nBody\n"
+        );
+        assert_eq!(
+            edit_parsed(
+                "+++\ntitle = \"a\"\ndraft = true\n+++\nBody\n",
+                |front_matter| {
+                    front_matter.remove("draft").unwrap();
+                }
+            ),
+            "+++\ntitle = \"a\"\n+++\nBody\n"
+        );
+        assert_eq!(
+            edit_parsed("---\r\ntitle: a\r\n---\r\nBody\r\n", |front_matter| {
+                front_matter.set("updated", "2021-02-03").unwrap()
+            }),
+            "---\r\ntitle: a\r\nupdated: 2021-02-03\r\n---\r\nBody\r\n"
+        );
+        assert_eq!(
+            edit_parsed("+++\r\ntitle = \"a\"\r\n+++\r\nBody\r\n", |front_matter| {
+                front_matter.insert("draft", true).unwrap();
+            }),
+            "+++\r\ntitle = \"a\"\r\ndraft = true\r\n+++\r\nBody\r\n"
+        );
+    }
+}
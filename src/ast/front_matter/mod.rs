@@ -0,0 +1,427 @@
+use super::{FrontmatterContent, Json, Position, Root, Toml, YAML};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use std::error::Error;
+use std::fmt;
+use yaml_rust2::{ScanError, Yaml, YamlLoader};
+
+mod edit;
+
+const NOT_A_MAP: &str = "front matter must be a map of keys to values";
+
+/// Error returned when front matter could not be read.
+/// It points at the line and column in the document, or in the value of the node when the node has no position.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrontMatterError {
+    message: String,
+    line: usize,
+    column: usize,
+    position: Option<Position>,
+}
+
+impl FrontMatterError {
+    // `line` and `column` are 1-indexed and relative to the value of the node at `position`,
+    // which starts `fence` lines below the start of the node
+    fn new(
+        message: impl Into<String>,
+        position: Option<Position>,
+        fence: usize,
+        line: usize,
+        column: usize,
+    ) -> FrontMatterError {
+        let offset = position.map_or(0, |position| position.start.line as usize - 1 + fence);
+        FrontMatterError {
+            message: message.into(),
+            line: offset + line,
+            column,
+            position,
+        }
+    }
+
+    /// A description of what went wrong.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The line (1-indexed) of the offending front matter.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The column (1-indexed) of the offending front matter.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// The position of the front matter node in the document, if it has one.
+    pub fn position(&self) -> Option<Position> {
+        self.position
+    }
+}
+
+impl fmt::Display for FrontMatterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at line {} column {}",
+            self.message, self.line, self.column
+        )
+    }
+}
+
+impl Error for FrontMatterError {}
+
+fn yaml_key(key: Yaml) -> Result<String, String> {
+    match key {
+        Yaml::String(key) | Yaml::Real(key) => Ok(key),
+        Yaml::Integer(key) => Ok(key.to_string()),
+        Yaml::Boolean(key) => Ok(key.to_string()),
+        Yaml::Null => Ok("null".to_string()),
+        other => Err(format!("unsupported key {:?}", other)),
+    }
+}
+
+fn yaml_value(yaml: Yaml) -> Result<Value, String> {
+    match yaml {
+        Yaml::Real(_) => Ok(Value::from(yaml.as_f64())),
+        Yaml::Integer(value) => Ok(Value::from(value)),
+        Yaml::String(value) => Ok(Value::String(value)),
+        Yaml::Boolean(value) => Ok(Value::Bool(value)),
+        Yaml::Array(values) => values
+            .into_iter()
+            .map(yaml_value)
+            .collect::<Result<_, _>>()
+            .map(Value::Array),
+        Yaml::Hash(entries) => entries
+            .into_iter()
+            .map(|(key, value)| Ok((yaml_key(key)?, yaml_value(value)?)))
+            .collect::<Result<_, _>>()
+            .map(Value::Object),
+        Yaml::Null => Ok(Value::Null),
+        Yaml::Alias(_) => Err("unknown alias".to_string()),
+        Yaml::BadValue => Err("invalid value".to_string()),
+    }
+}
+
+// 1-indexed line and column of a byte offset
+fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset];
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    (before.matches('\n').count() + 1, offset - line_start + 1)
+}
+
+impl YAML {
+    /// Reads the front matter into a map of keys to values, in the order they appear.
+    /// Empty front matter is an empty map.
+    pub fn map(&self) -> Result<Map<String, Value>, FrontMatterError> {
+        let source = String::from_utf8_lossy(&self.value);
+        let documents =
+            YamlLoader::load_from_str(&source).map_err(|error| self.scan_error(&error))?;
+        match documents.into_iter().next() {
+            // nothing but whitespace or comments
+            None | Some(Yaml::Null) => Ok(Map::new()),
+            Some(document) => match yaml_value(document) {
+                Ok(Value::Object(map)) => Ok(map),
+                Ok(_) => Err(self.error(NOT_A_MAP)),
+                Err(message) => Err(self.error(message)),
+            },
+        }
+    }
+
+    fn scan_error(&self, error: &ScanError) -> FrontMatterError {
+        FrontMatterError::new(
+            error.info(),
+            self.position,
+            1,
+            error.marker().line(),
+            error.marker().col() + 1,
+        )
+    }
+
+    // an error about the value as a whole, pointing at its first line
+    fn error(&self, message: impl Into<String>) -> FrontMatterError {
+        FrontMatterError::new(message, self.position, 1, 1, 1)
+    }
+}
+
+fn toml_table<'a>(entries: impl Iterator<Item = (&'a str, Value)>) -> Value {
+    Value::Object(
+        entries
+            .map(|(key, value)| (key.to_string(), value))
+            .collect(),
+    )
+}
+
+fn toml_item(item: &toml_edit::Item) -> Value {
+    match item {
+        toml_edit::Item::None => Value::Null,
+        toml_edit::Item::Value(value) => toml_value(value),
+        toml_edit::Item::Table(table) => {
+            toml_table(table.iter().map(|(key, item)| (key, toml_item(item))))
+        }
+        toml_edit::Item::ArrayOfTables(tables) => tables
+            .iter()
+            .map(|table| toml_table(table.iter().map(|(key, item)| (key, toml_item(item)))))
+            .collect(),
+    }
+}
+
+fn toml_value(value: &toml_edit::Value) -> Value {
+    match value {
+        toml_edit::Value::String(value) => Value::String(value.value().clone()),
+        toml_edit::Value::Integer(value) => Value::from(*value.value()),
+        toml_edit::Value::Float(value) => Value::from(*value.value()),
+        toml_edit::Value::Boolean(value) => Value::Bool(*value.value()),
+        // JSON has no dates so they are kept as written
+        toml_edit::Value::Datetime(value) => Value::String(value.value().to_string()),
+        toml_edit::Value::Array(values) => values.iter().map(toml_value).collect(),
+        toml_edit::Value::InlineTable(table) => {
+            toml_table(table.iter().map(|(key, value)| (key, toml_value(value))))
+        }
+    }
+}
+
+impl Toml {
+    /// Reads the front matter into a map of keys to values, in the order they appear.
+    /// Dates and times are strings in the format they were written in.
+    pub fn map(&self) -> Result<Map<String, Value>, FrontMatterError> {
+        match toml_item(self.document()?.as_item()) {
+            Value::Object(map) => Ok(map),
+            _ => unreachable!("a TOML document is a table"),
+        }
+    }
+
+    fn document(&self) -> Result<toml_edit::DocumentMut, FrontMatterError> {
+        let source = String::from_utf8_lossy(&self.value);
+        source.parse().map_err(|error: toml_edit::TomlError| {
+            let (line, column) = line_column(&source, error.span().map_or(0, |span| span.start));
+            FrontMatterError::new(error.message().trim_end(), self.position, 1, line, column)
+        })
+    }
+}
+
+impl Json {
+    /// Reads the front matter into a map of keys to values, in the order they appear.
+    pub fn map(&self) -> Result<Map<String, Value>, FrontMatterError> {
+        match serde_json::from_slice(&self.value) {
+            Ok(Value::Object(map)) => Ok(map),
+            Ok(_) => Err(FrontMatterError::new(NOT_A_MAP, self.position, 0, 1, 1)),
+            Err(error) => Err(self.syntax_error(&error)),
+        }
+    }
+
+    fn syntax_error(&self, error: &serde_json::Error) -> FrontMatterError {
+        // the location is kept separately
+        let message = error.to_string();
+        let message = match message.rfind(" at line ") {
+            Some(location) => message[..location].to_string(),
+            None => message,
+        };
+        // the object starts on the first line of the node
+        FrontMatterError::new(message, self.position, 0, error.line(), error.column())
+    }
+}
+
+impl FrontmatterContent {
+    /// Reads the front matter into a map of keys to values, whatever its format.
+    pub fn map(&self) -> Result<Map<String, Value>, FrontMatterError> {
+        match self {
+            FrontmatterContent::YAML(yaml) => yaml.map(),
+            FrontmatterContent::Toml(toml) => toml.map(),
+            FrontmatterContent::Json(json) => json.map(),
+        }
+    }
+
+    /// The position of the front matter in the document, fences included.
+    pub fn position(&self) -> Option<Position> {
+        match self {
+            FrontmatterContent::YAML(yaml) => yaml.position,
+            FrontmatterContent::Toml(toml) => toml.position,
+            FrontmatterContent::Json(json) => json.position,
+        }
+    }
+}
+
+impl Root {
+    /// Deserializes the front matter into `T` from the values read by [`FrontmatterContent::map`],
+    /// or returns `None` when the document has no front matter.
+    /// When the values do not fit `T` the error points at the first line of the front matter.
+    pub fn front_matter_as<T: DeserializeOwned>(&self) -> Result<Option<T>, FrontMatterError> {
+        let front_matter = match self.front_matter() {
+            Some(front_matter) => front_matter,
+            None => return Ok(None),
+        };
+        serde_json::from_value(Value::Object(front_matter.map()?))
+            .map(Some)
+            .map_err(|error| {
+                FrontMatterError::new(error.to_string(), front_matter.position(), 0, 1, 1)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::Point;
+
+    fn yaml(value: &str) -> YAML {
+        YAML {
+            value: value.as_bytes().to_vec(),
+            // the opening fence is on the first line
+            position: Some(Position::new(
+                Point::new(1, 1, Some(0)),
+                Point::new(4, 4, None),
+                None,
+            )),
+        }
+    }
+
+    #[test]
+    fn map() {
+        let map = yaml("title: Hello\ntags: [a, b]\ndraft: false\nweight: 1.5\n# comment\nauthor:\n  name: Sean")
+            .map()
+            .unwrap();
+        assert_eq!(
+            map.keys().collect::<Vec<_>>(),
+            vec!["title", "tags", "draft", "weight", "author"]
+        );
+        assert_eq!(map["title"], "Hello");
+        assert_eq!(map["tags"], serde_json::json!(["a", "b"]));
+        assert_eq!(map["draft"], false);
+        assert_eq!(map["weight"], 1.5);
+        assert_eq!(map["author"]["name"], "Sean");
+
+        assert!(yaml("").map().unwrap().is_empty());
+        assert!(yaml("# just a comment").map().unwrap().is_empty());
+    }
+
+    #[test]
+    fn toml_map() {
+        let toml = Toml {
+            value: b"title = \"Hello\"\ndate = 2021-01-02\ntags = [\"a\", \"b\"]\n\n[author]\nname = \"Sean\"\n\n[[links]]\nurl = \"/a\"".to_vec(),
+            position: None,
+        };
+        assert_eq!(
+            Value::Object(toml.map().unwrap()),
+            serde_json::json!({
+                "title": "Hello",
+                "date": "2021-01-02",
+                "tags": ["a", "b"],
+                "author": {"name": "Sean"},
+                "links": [{"url": "/a"}]
+            })
+        );
+
+        let toml = Toml {
+            value: b"title = \"Hello\"\ntags = [a]".to_vec(),
+            position: yaml("").position,
+        };
+        let error = toml.map().unwrap_err();
+        assert_eq!((error.line(), error.column()), (3, 9));
+    }
+
+    #[test]
+    fn json_map() {
+        let json = Json {
+            value: b"{\n  \"title\": \"Hello\",\n  \"tags\": [\"a\", \"b\"]\n}".to_vec(),
+            position: None,
+        };
+        let map = json.map().unwrap();
+        assert_eq!(map.keys().collect::<Vec<_>>(), vec!["title", "tags"]);
+        assert_eq!(map["tags"], serde_json::json!(["a", "b"]));
+
+        let json = Json {
+            value: b"{\n  \"title\": \"Hello\"\n  \"tags\": []\n}".to_vec(),
+            position: yaml("").position,
+        };
+        let error = json.map().unwrap_err();
+        // the object starts on the first line of the document
+        assert_eq!((error.line(), error.column()), (3, 3));
+        assert_eq!(error.message(), "expected `,` or `}`");
+    }
+
+    #[derive(serde_derive::Deserialize, Debug, PartialEq)]
+    struct Adr {
+        status: String,
+        date: String,
+        #[serde(default)]
+        deciders: Vec<String>,
+    }
+
+    fn root(front_matter: FrontmatterContent) -> Root {
+        Root::new(vec![
+            crate::ast::FlowContentFrontmatter::FrontmatterContent(front_matter),
+        ])
+    }
+
+    #[test]
+    fn front_matter_as() {
+        let adr = root(FrontmatterContent::YAML(yaml(
+            "status: accepted\ndate: 2021-01-02\ndeciders: [Sean]",
+        )))
+        .front_matter_as::<Adr>()
+        .unwrap();
+        assert_eq!(
+            adr,
+            Some(Adr {
+                status: "accepted".to_string(),
+                date: "2021-01-02".to_string(),
+                deciders: vec!["Sean".to_string()],
+            })
+        );
+
+        let toml = Toml {
+            value: b"status = \"proposed\"\ndate = 2021-01-02".to_vec(),
+            position: None,
+        };
+        let adr = root(FrontmatterContent::Toml(toml))
+            .front_matter_as::<Adr>()
+            .unwrap()
+            .unwrap();
+        assert_eq!(adr.date, "2021-01-02");
+        assert!(adr.deciders.is_empty());
+
+        assert_eq!(Root::new(vec![]).front_matter_as::<Adr>(), Ok(None));
+    }
+
+    #[test]
+    fn front_matter_as_errors() {
+        let front_matter = yaml("date: 2021-01-02");
+        let position = front_matter.position;
+        let error = root(FrontmatterContent::YAML(front_matter))
+            .front_matter_as::<Adr>()
+            .unwrap_err();
+        assert_eq!(error.message(), "missing field `status`");
+        assert_eq!(error.position(), position);
+        assert_eq!((error.line(), error.column()), (1, 1));
+
+        // errors reading the front matter keep their line
+        let error = root(FrontmatterContent::YAML(yaml("status: a\nstatus: b: c")))
+            .front_matter_as::<Adr>()
+            .unwrap_err();
+        assert_eq!(error.line(), 3);
+        assert_eq!(error.position(), position);
+    }
+
+    #[test]
+    fn errors() {
+        let error = yaml("title: Hello\nbad: a: b").map().unwrap_err();
+        assert_eq!((error.line(), error.column()), (3, 7));
+        assert_eq!(
+            error.to_string(),
+            "mapping values are not allowed in this context at line 3 column 7"
+        );
+
+        let error = yaml("- a\n- b").map().unwrap_err();
+        assert_eq!(
+            error.message(),
+            "front matter must be a map of keys to values"
+        );
+        assert_eq!(error.line(), 2);
+
+        // without a position lines are counted from the start of the value
+        let mut generated = yaml("a: 1\nb: 2\nc: d: e");
+        generated.position = None;
+        assert_eq!(generated.map().unwrap_err().line(), 3);
+    }
+}
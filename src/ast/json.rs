@@ -0,0 +1,268 @@
+// conversions hand a rejected node back by value so it can be tried against the next category
+#![allow(clippy::result_large_err)]
+
+use super::{
+    AlignType, Content, FlowContent, FlowContentFrontmatter, FrontmatterContent, ListContent,
+    MdastContent, NodeType, PhrasingContent, Root, RowContent, StaticPhrasingContent, TableContent,
+};
+use serde::de::{Deserialize, Deserializer, Error};
+use serde::ser::{Serialize, Serializer};
+use serde_derive::Deserialize;
+use std::fmt;
+
+// mdast JSON has a flat `type` field on every node while the tree nests the content categories in enums.
+// Nodes serialize their own type and the category enums are untagged so nothing of them ends up in the JSON.
+// Deserializing goes through NodeType, which looks at the type, after which the node is placed in the
+// category the parent expects. Nodes that are not allowed there are rejected.
+
+/// (De)serializes byte values as the strings mdast uses.
+pub(super) mod utf8 {
+    use serde::de::{Deserialize, Deserializer};
+    use serde::ser::Serializer;
+
+    pub(crate) fn serialize<S: Serializer>(value: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&String::from_utf8_lossy(value))
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<u8>, D::Error> {
+        String::deserialize(deserializer).map(String::into_bytes)
+    }
+
+    pub(crate) mod option {
+        use serde::de::{Deserialize, Deserializer};
+        use serde::ser::Serializer;
+
+        pub(crate) fn serialize<S: Serializer>(
+            value: &Option<Vec<u8>>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match value {
+                Some(value) => super::serialize(value, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<Vec<u8>>, D::Error> {
+            Option::<String>::deserialize(deserializer).map(|value| value.map(String::into_bytes))
+        }
+    }
+}
+
+// Each conversion hands the node back when it does not belong to the category.
+
+impl Content {
+    fn from_node(node: NodeType) -> Result<Content, NodeType> {
+        match node {
+            NodeType::Definition(definition) => Ok(Content::Definition(definition)),
+            NodeType::Paragraph(paragraph) => Ok(Content::Paragraph(paragraph)),
+            node => Err(node),
+        }
+    }
+}
+
+impl FlowContent {
+    fn from_node(node: NodeType) -> Result<FlowContent, NodeType> {
+        match node {
+            NodeType::BlockQuote(quote) => Ok(FlowContent::BlockQuote(quote)),
+            NodeType::Code(code) => Ok(FlowContent::Code(code)),
+            NodeType::Heading(heading) => Ok(FlowContent::Heading(heading)),
+            NodeType::HTML(html) => Ok(FlowContent::HTML(html)),
+            NodeType::List(list) => Ok(FlowContent::List(list)),
+            NodeType::ThematicBreak(thematic_break) => {
+                Ok(FlowContent::ThematicBreak(thematic_break))
+            }
+            NodeType::Table(table) => Ok(FlowContent::Table(table)),
+            NodeType::FootnoteDefinition(definition) => {
+                Ok(FlowContent::FootnoteDefinition(definition))
+            }
+            node => Content::from_node(node).map(FlowContent::Content),
+        }
+    }
+}
+
+impl ListContent {
+    fn from_node(node: NodeType) -> Result<ListContent, NodeType> {
+        match node {
+            NodeType::ListItem(item) => Ok(ListContent::ListItem(item)),
+            node => Err(node),
+        }
+    }
+}
+
+impl TableContent {
+    fn from_node(node: NodeType) -> Result<TableContent, NodeType> {
+        match node {
+            NodeType::TableRow(row) => Ok(TableContent::TableRow(row)),
+            node => Err(node),
+        }
+    }
+}
+
+impl RowContent {
+    fn from_node(node: NodeType) -> Result<RowContent, NodeType> {
+        match node {
+            NodeType::TableCell(cell) => Ok(RowContent::TableCell(cell)),
+            node => Err(node),
+        }
+    }
+}
+
+impl StaticPhrasingContent {
+    fn from_node(node: NodeType) -> Result<StaticPhrasingContent, NodeType> {
+        match node {
+            NodeType::Break(line_break) => Ok(StaticPhrasingContent::Break(line_break)),
+            NodeType::Emphasis(emphasis) => Ok(StaticPhrasingContent::Emphasis(emphasis)),
+            NodeType::HTML(html) => Ok(StaticPhrasingContent::HTML(html)),
+            NodeType::Image(image) => Ok(StaticPhrasingContent::Image(image)),
+            NodeType::ImageReference(reference) => {
+                Ok(StaticPhrasingContent::ImageReference(reference))
+            }
+            NodeType::InlineCode(code) => Ok(StaticPhrasingContent::InlineCode(code)),
+            NodeType::Strong(strong) => Ok(StaticPhrasingContent::Strong(strong)),
+            NodeType::Text(text) => Ok(StaticPhrasingContent::Text(text)),
+            NodeType::Delete(delete) => Ok(StaticPhrasingContent::Delete(delete)),
+            NodeType::Footnote(footnote) => Ok(StaticPhrasingContent::Footnote(footnote)),
+            NodeType::FootnoteReference(reference) => {
+                Ok(StaticPhrasingContent::FootnoteReference(reference))
+            }
+            node => Err(node),
+        }
+    }
+}
+
+impl PhrasingContent {
+    fn from_node(node: NodeType) -> Result<PhrasingContent, NodeType> {
+        match node {
+            NodeType::Link(link) => Ok(PhrasingContent::Link(link)),
+            NodeType::LinkReference(reference) => Ok(PhrasingContent::LinkReference(reference)),
+            node => {
+                StaticPhrasingContent::from_node(node).map(PhrasingContent::StaticPhrasingContent)
+            }
+        }
+    }
+}
+
+impl FrontmatterContent {
+    fn from_node(node: NodeType) -> Result<FrontmatterContent, NodeType> {
+        match node {
+            NodeType::YAML(yaml) => Ok(FrontmatterContent::YAML(yaml)),
+            NodeType::Toml(toml) => Ok(FrontmatterContent::Toml(toml)),
+            NodeType::Json(json) => Ok(FrontmatterContent::Json(json)),
+            node => Err(node),
+        }
+    }
+}
+
+impl FlowContentFrontmatter {
+    fn from_node(node: NodeType) -> Result<FlowContentFrontmatter, NodeType> {
+        FrontmatterContent::from_node(node)
+            .map(FlowContentFrontmatter::FrontmatterContent)
+            .or_else(|node| FlowContent::from_node(node).map(FlowContentFrontmatter::FlowContent))
+    }
+}
+
+impl MdastContent {
+    fn from_node(node: NodeType) -> Result<MdastContent, NodeType> {
+        FlowContent::from_node(node)
+            .map(MdastContent::FlowContent)
+            .or_else(|node| ListContent::from_node(node).map(MdastContent::ListContent))
+            .or_else(|node| PhrasingContent::from_node(node).map(MdastContent::PhrasingContent))
+            .or_else(|node| TableContent::from_node(node).map(MdastContent::TableContent))
+            .or_else(|node| RowContent::from_node(node).map(MdastContent::RowContent))
+    }
+}
+
+macro_rules! deserialize_content {
+    ($content:ident, $category:expr) => {
+        impl<'de> Deserialize<'de> for $content {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                $content::from_node(NodeType::deserialize(deserializer)?).map_err(|node| {
+                    D::Error::custom(format!("expected {}, found `{}`", $category, node.name()))
+                })
+            }
+        }
+    };
+}
+
+deserialize_content!(Content, "content");
+deserialize_content!(FlowContent, "flow content");
+deserialize_content!(ListContent, "list content");
+deserialize_content!(TableContent, "table content");
+deserialize_content!(RowContent, "row content");
+deserialize_content!(StaticPhrasingContent, "static phrasing content");
+deserialize_content!(PhrasingContent, "phrasing content");
+deserialize_content!(FrontmatterContent, "frontmatter content");
+deserialize_content!(FlowContentFrontmatter, "flow or frontmatter content");
+deserialize_content!(MdastContent, "mdast content");
+
+// columns without an alignment are null
+impl Serialize for AlignType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            AlignType::Left => serializer.serialize_str("left"),
+            AlignType::Right => serializer.serialize_str("right"),
+            AlignType::Center => serializer.serialize_str("center"),
+            AlignType::None => serializer.serialize_none(),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for AlignType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match Option::<String>::deserialize(deserializer)?.as_deref() {
+            Some("left") => Ok(AlignType::Left),
+            Some("right") => Ok(AlignType::Right),
+            Some("center") => Ok(AlignType::Center),
+            None => Ok(AlignType::None),
+            Some(other) => Err(D::Error::unknown_variant(
+                other,
+                &["left", "right", "center"],
+            )),
+        }
+    }
+}
+
+/// Error returned when mdast JSON could not be turned into a tree, such as when it is not valid JSON,
+/// contains a node of an unknown type or a node where it is not allowed.
+/// Errors in the syntax of the JSON include the line and column where they were found.
+#[derive(Debug)]
+pub struct JsonError {
+    error: serde_json::Error,
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl std::error::Error for JsonError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+// only a root can be the top of the tree
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum Tree {
+    Root(Root),
+}
+
+/// Reads a tree from mdast JSON, as produced by remark with `JSON.stringify(tree)`.
+/// The top of the tree must be a root, use [`node_from_mdast_json`] for any other node.
+pub fn from_mdast_json(json: &str) -> Result<Root, JsonError> {
+    match serde_json::from_str(json) {
+        Ok(Tree::Root(root)) => Ok(root),
+        Err(error) => Err(JsonError { error }),
+    }
+}
+
+/// Reads a node of any type from mdast JSON, such as a subtree a remark plugin works on.
+pub fn node_from_mdast_json(json: &str) -> Result<NodeType, JsonError> {
+    serde_json::from_str(json).map_err(|error| JsonError { error })
+}
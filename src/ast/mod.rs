@@ -2,6 +2,17 @@ use nom::lib::std::fmt::Formatter;
 use serde_derive::{Deserialize, Serialize};
 use std::{assert, fmt};
 
+mod code_meta;
+mod footnote;
+mod front_matter;
+mod json;
+
+pub use code_meta::{CodeMetaError, MetaAttribute, MetaAttributeKind};
+pub use footnote::FootnoteReport;
+pub use front_matter::FrontMatterError;
+use json::utf8;
+pub use json::{from_mdast_json, node_from_mdast_json, JsonError};
+
 // TODO: anything from comrak that might be worth including?
 // - NodeValue aka NodeType?
 // - DescriptionList
@@ -19,24 +30,25 @@ use std::{assert, fmt};
 // look at https://doc.rust-lang.org/edition-guide/rust-2018/trait-system/associated-constants.html
 // regarding constants like type = "Root"
 
-trait NType {
-    const TYPE: String;
-}
-
+// trait NType {
+//     const TYPE: String;
+// }
 
 // trait Parent {
 //     fn add_child(child: MdastContent);
 // }
 
-trait Parent<T> {
-    fn add_child(child: T);
-}
+// trait Parent<T> {
+//     fn add_child(child: T);
+// }
 
 // I dont like NoteType because its more then the type
 // I'm also not a fan of NodeValue
 // Maybe NodeData. NodeInfo
-// #[derive(Serialize, Deserialize)]
-// #[serde(tag = "type", rename_all = "camelCase")] // BlockQuote doesnt follow this as the type is "blockquote"
+/// A node of any type, told apart by the `type` field of its mdast JSON.
+/// Nodes serialize themselves with their type so this is only used to deserialize them.
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "camelCase")]
 pub enum NodeType {
     Root(Root),
 
@@ -44,14 +56,17 @@ pub enum NodeType {
 
     Heading(Heading),
 
-    ThematicBreak,
+    ThematicBreak(ThematicBreak),
 
+    // BlockQuote doesnt follow camelCase as the type is "blockquote"
+    #[serde(rename = "blockquote")]
     BlockQuote(BlockQuote),
 
     List(List),
 
     ListItem(ListItem),
 
+    #[serde(rename = "html")]
     HTML(HTML),
 
     Code(Code),
@@ -75,10 +90,66 @@ pub enum NodeType {
     LinkReference(LinkReference),
 
     ImageReference(ImageReference),
+
+    #[serde(rename = "yaml")]
+    YAML(YAML),
+
+    Toml(Toml),
+
+    Json(Json),
+
+    Table(Table),
+
+    TableRow(TableRow),
+
+    TableCell(TableCell),
+
+    Delete(Delete),
+
+    FootnoteDefinition(FootnoteDefinition),
+
+    Footnote(Footnote),
+
+    FootnoteReference(FootnoteReference),
     // TODO: add extensions
 }
 
 impl NodeType {
+    /// The mdast type of the node, as found in its `type` field.
+    pub fn name(&self) -> &'static str {
+        match self {
+            NodeType::Root(_) => "root",
+            NodeType::Paragraph(_) => "paragraph",
+            NodeType::Heading(_) => "heading",
+            NodeType::ThematicBreak(_) => "thematicBreak",
+            NodeType::BlockQuote(_) => "blockquote",
+            NodeType::List(_) => "list",
+            NodeType::ListItem(_) => "listItem",
+            NodeType::HTML(_) => "html",
+            NodeType::Code(_) => "code",
+            NodeType::Definition(_) => "definition",
+            NodeType::Text(_) => "text",
+            NodeType::Emphasis(_) => "emphasis",
+            NodeType::Strong(_) => "strong",
+            NodeType::InlineCode(_) => "inlineCode",
+            NodeType::Break(_) => "break",
+            NodeType::Link(_) => "link",
+            NodeType::Image(_) => "image",
+            NodeType::LinkReference(_) => "linkReference",
+            NodeType::ImageReference(_) => "imageReference",
+            NodeType::YAML(_) => "yaml",
+            NodeType::Toml(_) => "toml",
+            NodeType::Json(_) => "json",
+            NodeType::Table(_) => "table",
+            NodeType::TableRow(_) => "tableRow",
+            NodeType::TableCell(_) => "tableCell",
+            NodeType::Delete(_) => "delete",
+            NodeType::FootnoteDefinition(_) => "footnoteDefinition",
+            NodeType::Footnote(_) => "footnote",
+            NodeType::FootnoteReference(_) => "footnoteReference",
+        }
+    }
+
     // /// Indicates whether this node is a block node or inline node.
     // pub fn block(&self) -> bool {
     //     matches!(*self, NodeValue::Document
@@ -120,16 +191,18 @@ pub struct Data<T> {
 }
 
 /// Represents one place in a source file.
-#[derive(Serialize, Deserialize, Debug)]
+/// The markdown parser counts columns and offsets in UTF-16 code units, like remark.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 pub struct Point {
     /// The line field (1-indexed integer) represents a line in a source file.
-    line: u64,
+    pub line: u64,
 
     /// The column field (1-indexed integer) represents a column in a source file.
-    column: u64,
+    pub column: u64,
 
     /// The offset field (0-indexed integer) represents a character in a source file.
-    offset: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub offset: Option<u64>,
 }
 
 impl Point {
@@ -148,25 +221,27 @@ impl Point {
 /// Represents the location of a node in a source file.
 /// If the syntactic unit represented by a node is not present in the source file at the time of parsing,
 /// the node is said to be generated and it must not have positional information.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 pub struct Position {
     /// The start field represents the place of the first character of the parsed source region.
-    start: Point,
+    pub start: Point,
 
     /// The end field represents the place of the first character after the parsed source region,
     /// whether it exists or not.
-    end: Point,
+    pub end: Point,
 
     // TODO: remark/unify doesnt appear to include this in the json output
+    // older versions of remark write a list of columns here which is ignored when reading json
     /// The indent field (1-indexed integer) represents the start column at each index
     /// (plus start line) in the source region, for elements that span multiple lines.
-    indent: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none", skip_deserializing)]
+    pub indent: Option<u32>,
 }
 
 impl Position {
     pub fn new(start: Point, end: Point, indent: Option<u32>) -> Position {
-        if indent.is_some() {
-            assert!(indent.unwrap() >= 1);
+        if let Some(indent) = indent {
+            assert!(indent >= 1);
         }
 
         Position { start, end, indent }
@@ -180,8 +255,10 @@ pub struct Node {
     pub node_type: String,
 
     // aka Data
+    #[serde(with = "utf8::option", default)]
     pub value: Option<Vec<u8>>,
 
+    #[serde(skip_serializing_if = "Option::is_none", default)]
     pub position: Option<Position>,
 }
 
@@ -246,18 +323,142 @@ pub struct Literal {
 // implements Parent
 // Root can be used as the root of a tree, never as a child.
 // Its content model is not limited to flow content, but can contain any mdast content with the restriction that all content must be of the same category.
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(tag = "type", rename = "root")]
 pub struct Root {
     // type: "root"
+
+    // children: [FlowContentFrontmatter]
+    pub children: Vec<FlowContentFrontmatter>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub position: Option<Position>,
+}
+
+impl Root {
+    pub fn new(children: Vec<FlowContentFrontmatter>) -> Root {
+        Root {
+            children,
+            position: None,
+        }
+    }
+
+    /// Returns the frontmatter of the document, if any.
+    /// Frontmatter can only exist as the head of the tree so only the first child is checked.
+    pub fn front_matter(&self) -> Option<&FrontmatterContent> {
+        match self.children.first() {
+            Some(FlowContentFrontmatter::FrontmatterContent(f)) => Some(f),
+            _ => None,
+        }
+    }
+
+    /// The frontmatter of the document, to be edited.
+    pub fn front_matter_mut(&mut self) -> Option<&mut FrontmatterContent> {
+        match self.children.first_mut() {
+            Some(FlowContentFrontmatter::FrontmatterContent(f)) => Some(f),
+            _ => None,
+        }
+    }
+
+    /// Iterates over the flow content of the document, skipping any frontmatter.
+    pub fn flow_content(&self) -> impl Iterator<Item = &FlowContent> {
+        self.children.iter().filter_map(|c| match c {
+            FlowContentFrontmatter::FlowContent(f) => Some(f),
+            _ => None,
+        })
+    }
+
+    /// Returns every definition in the document in source order, including those nested in block quotes, lists
+    /// and footnote definitions.
+    pub fn definitions(&self) -> Vec<&Definition> {
+        fn collect<'a>(content: &'a FlowContent, definitions: &mut Vec<&'a Definition>) {
+            match content {
+                FlowContent::Content(Content::Definition(d)) => definitions.push(d),
+                FlowContent::BlockQuote(b) => {
+                    for child in &b.children {
+                        collect(child, definitions);
+                    }
+                }
+                FlowContent::FootnoteDefinition(f) => {
+                    for child in &f.children {
+                        collect(child, definitions);
+                    }
+                }
+                FlowContent::List(l) => {
+                    for ListContent::ListItem(item) in &l.children {
+                        for child in &item.children {
+                            collect(child, definitions);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut definitions = Vec::new();
+        for content in self.flow_content() {
+            collect(content, &mut definitions);
+        }
+        definitions
+    }
+
+    /// Returns every task list item in the document in source order, including those in nested lists.
+    /// Their `checked` field tells whether the task is done.
+    pub fn tasks(&self) -> Vec<&ListItem> {
+        fn collect<'a>(content: &'a FlowContent, tasks: &mut Vec<&'a ListItem>) {
+            match content {
+                FlowContent::BlockQuote(b) => {
+                    for child in &b.children {
+                        collect(child, tasks);
+                    }
+                }
+                FlowContent::FootnoteDefinition(f) => {
+                    for child in &f.children {
+                        collect(child, tasks);
+                    }
+                }
+                FlowContent::List(l) => {
+                    for ListContent::ListItem(item) in &l.children {
+                        if item.checked.is_some() {
+                            tasks.push(item);
+                        }
+                        for child in &item.children {
+                            collect(child, tasks);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut tasks = Vec::new();
+        for content in self.flow_content() {
+            collect(content, &mut tasks);
+        }
+        tasks
+    }
+
+    /// Resolves the association of a LinkReference or ImageReference to its Definition.
+    /// When several definitions share an identifier the first one wins.
+    pub fn resolve(&self, association: &Association) -> Option<&Definition> {
+        self.definitions()
+            .into_iter()
+            .find(|d| d.association.identifier == association.identifier)
+    }
 }
 
 // implements Parent
 // Paragraph (Parent) represents a unit of discourse dealing with a particular point or idea.
 // Paragraph can be used where content is expected. Its content model is phrasing content.
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename = "paragraph")]
 pub struct Paragraph {
     // type: "paragraph"
     // children: [PhrasingContent]
-    children: Vec<PhrasingContent>,
+    pub children: Vec<PhrasingContent>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub position: Option<Position>,
 }
 
 // implements Parent
@@ -265,6 +466,7 @@ pub struct Paragraph {
 // Heading can be used where flow content is expected. Its content model is phrasing content.
 // A depth field must be present. A value of 1 is said to be the highest rank and 6 the lowest.
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename = "heading")]
 pub struct Heading {
     // type: "heading"
 
@@ -276,7 +478,12 @@ pub struct Heading {
     pub children: Vec<PhrasingContent>,
 
     /// Whether the heading is setext (if not, ATX).
+    /// This is not part of mdast so it is left out of the JSON.
+    #[serde(skip)]
     pub setext: bool,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub position: Option<Position>,
 }
 
 impl Heading {
@@ -284,13 +491,14 @@ impl Heading {
         if setext {
             assert!(depth == 1 || depth == 2);
         } else {
-            assert!(depth >= 1 && depth <= 6);
+            assert!((1..=6).contains(&depth));
         }
 
         Heading {
             depth,
             setext,
             children: Vec::new(),
+            position: None,
         }
     }
 }
@@ -299,58 +507,78 @@ impl Heading {
 // ThematicBreak (Node) represents a thematic break, such as a scene change in a story, a transition to another topic, or a new document.
 // ThematicBreak can be used where flow content is expected. It has no content model.
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename = "thematicBreak")]
 pub struct ThematicBreak {
     // type: "thematicBreak"
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub position: Option<Position>,
 }
 
 // implements Parent
 // Blockquote (Parent) represents a section quoted from somewhere else.
 // Blockquote can be used where flow content is expected. Its content model is also flow content.
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename = "blockquote")]
 pub struct BlockQuote {
     // type: "blockquote"
 
     // children: [FlowContent]
-    children: Vec<Node>,
+    pub children: Vec<FlowContent>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub position: Option<Position>,
 }
 
 // implements Parent
 // List (Parent) represents a list of items.
 // List can be used where flow content is expected. Its content model is list content.
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename = "list")]
 pub struct List {
     // type: "list"
 
     // An ordered field can be present.
     // It represents that the items have been intentionally ordered (when true),
     // or that the order of items is not important (when false or not present).
-    ordered: Option<bool>,
+    pub ordered: Option<bool>,
 
     // A start field can be present. It represents, when the ordered field is true, the starting number of the list.
-    start: Option<u64>,
+    pub start: Option<u64>,
 
     // A spread field can be present. It represents that one or more of its children are separated with a blank line from its siblings (when true), or not (when false or not present).
-    spread: Option<bool>,
+    pub spread: Option<bool>,
 
     // is spread different then loose and tight
     // https://github.github.com/gfm/#tight
 
     // children: [ListContent]
-    children: Vec<Node>,
+    pub children: Vec<ListContent>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub position: Option<Position>,
 }
 
 // implements Parent
 // ListItem (Parent) represents an item in a List.
 // ListItem can be used where list content is expected. Its content model is flow content.
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename = "listItem")]
 pub struct ListItem {
     // type: "listItem"
 
     // A spread field can be present. It represents that the item contains two or more children separated by a blank line (when true), or not (when false or not present).
-    spread: Option<bool>,
+    pub spread: Option<bool>,
+
+    // In GFM, a checked field can be present.
+    // It represents whether the item is done (when true), not done (when false), or indeterminate or not applicable (when null or not present).
+    // remark writes null for items that are not tasks.
+    pub checked: Option<bool>,
 
     // children: [FlowContent]
-    children: Vec<Node>,
+    pub children: Vec<FlowContent>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub position: Option<Position>,
 }
 
 // implements literal
@@ -358,9 +586,15 @@ pub struct ListItem {
 // HTML can be used where flow or phrasing content is expected. Its content is represented by its value field.
 // HTML nodes do not have the restriction of being valid or complete HTML ([HTML]) constructs.
 #[derive(Serialize, Deserialize, Debug)]
+#[allow(clippy::upper_case_acronyms)]
+#[serde(tag = "type", rename = "html")]
 pub struct HTML {
     // type: "html"
-    value: Vec<u8>,
+    #[serde(with = "utf8")]
+    pub value: Vec<u8>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub position: Option<Position>,
 }
 
 // implements literal
@@ -368,6 +602,7 @@ pub struct HTML {
 // Code can be used where flow content is expected. Its content is represented by its value field.
 // This node relates to the phrasing content concept InlineCode.
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename = "code")]
 pub struct Code {
     // type: "code"
 
@@ -379,7 +614,21 @@ pub struct Code {
     // It represents custom information relating to the node.
     pub meta: Option<String>,
 
+    // The lang and meta as written in the source when they differ from the values, which have backslash
+    // escapes and character references resolved, and where the meta starts in the source. The parser
+    // sets them to place meta attributes, which code read from JSON or built by hand cannot do.
+    #[serde(skip)]
+    pub(crate) raw_lang: Option<String>,
+    #[serde(skip)]
+    pub(crate) raw_meta: Option<String>,
+    #[serde(skip)]
+    pub(crate) meta_start: Option<Point>,
+
+    #[serde(with = "utf8")]
     pub value: Vec<u8>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub position: Option<Position>,
 }
 
 // implements node
@@ -389,20 +638,75 @@ pub struct Code {
 // Definition should be associated with LinkReferences and ImageReferences.
 // TODO: what the heck is a mixin and how to model that in rust?
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename = "definition")]
 pub struct Definition {
     // type: "definition"
+    pub url: String,
+    pub title: Option<String>,
+
+    // The url and title as written in the source when they differ from the values, which have backslash
+    // escapes and character references resolved. They are not part of mdast so they are not serialized.
+    #[serde(skip)]
+    pub raw_url: Option<String>,
+    #[serde(skip)]
+    pub raw_title: Option<String>,
+    #[serde(flatten)]
+    pub association: Association,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub position: Option<Position>,
+}
+
+impl Code {
+    pub fn new(lang: Option<String>, meta: Option<String>, value: Vec<u8>) -> Code {
+        Code {
+            lang,
+            meta,
+            raw_lang: None,
+            raw_meta: None,
+            meta_start: None,
+            value,
+            position: None,
+        }
+    }
+
+    /// The lang as written in the source, with its backslash escapes and character references.
+    pub fn raw_lang(&self) -> Option<&str> {
+        self.raw_lang.as_deref().or(self.lang.as_deref())
+    }
+
+    /// The meta as written in the source, with its backslash escapes and character references.
+    pub fn raw_meta(&self) -> Option<&str> {
+        self.raw_meta.as_deref().or(self.meta.as_deref())
+    }
+}
+
+impl Resource for Definition {
+    fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    fn title(&self) -> Option<String> {
+        self.title.clone()
+    }
 }
 
 // implements literal
 // Text (Literal) represents everything that is just text.
 // Text can be used where phrasing content is expected. Its content is represented by its value field.
 #[derive(Serialize, Deserialize, Debug)]
-#[serde(tag = "type")]
+#[serde(tag = "type", rename = "text")]
 pub struct Text {
     // type: "text"
-    // #[serde(rename = "type")]
-    // pub node_type: String,
+    #[serde(with = "utf8::option", default)]
     pub value: Option<Vec<u8>>,
+
+    // The text as written in the source when it differs from the value, which has backslash escapes and
+    // character references resolved. It is not part of mdast so it is not serialized.
+    #[serde(skip)]
+    pub raw: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
     pub position: Option<Position>,
 }
 
@@ -411,25 +715,30 @@ pub struct Text {
 // Emphasis (Parent) represents stress emphasis of its contents.
 // Emphasis can be used where phrasing content is expected. Its content model is transparent content.
 #[derive(Serialize, Deserialize, Debug)]
-// #[serde(tag = "type", rename_all(serialize = "lowercase"))]
+#[serde(tag = "type", rename = "emphasis")]
 pub struct Emphasis {
     // type: "emphasis"
 
     // children: [TransparentContent]
-    pub children: Vec<Node>,
+    pub children: Vec<PhrasingContent>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub position: Option<Position>,
 }
 
 // implements Parent
 // Strong (Parent) represents strong importance, seriousness, or urgency for its contents.
 // Strong can be used where phrasing content is expected. Its content model is transparent content.
 #[derive(Serialize, Deserialize, Debug)]
-// #[serde(tag = "type", rename_all(serialize = "lowercase"))]
-// #[serde(rename_all(serialize = "..."))]
+#[serde(tag = "type", rename = "strong")]
 pub struct Strong {
     // type: "strong"
 
     // children: [TransparentContent]
-    pub children: Vec<Node>,
+    pub children: Vec<PhrasingContent>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub position: Option<Position>,
 }
 
 // implements literal
@@ -438,17 +747,25 @@ pub struct Strong {
 // This node relates to the flow content concept Code.
 // https://github.github.com/gfm/#code-spans
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename = "inlineCode")]
 pub struct InlineCode {
     // type: "inlineCode"
-    value: Vec<u8>,
+    #[serde(with = "utf8")]
+    pub value: Vec<u8>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub position: Option<Position>,
 }
 
 // implements Node
 // Break (Node) represents a line break, such as in poems or addresses.
 // Break can be used where phrasing content is expected. It has no content model
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename = "break")]
 pub struct Break {
     // type: "break"
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub position: Option<Position>,
 }
 
 // implements Parent
@@ -457,11 +774,34 @@ pub struct Break {
 // Link can be used where phrasing content is expected. Its content model is static phrasing content.
 // Link includes the mixin Resource.
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename = "link")]
 pub struct Link {
     // type: "link"
+    pub url: String,
+    pub title: Option<String>,
+
+    // The url and title as written in the source when they differ from the values, which have backslash
+    // escapes and character references resolved. They are not part of mdast so they are not serialized.
+    #[serde(skip)]
+    pub raw_url: Option<String>,
+    #[serde(skip)]
+    pub raw_title: Option<String>,
 
     // children: [StaticPhrasingContent]
-    children: Vec<StaticPhrasingContent>,
+    pub children: Vec<StaticPhrasingContent>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub position: Option<Position>,
+}
+
+impl Resource for Link {
+    fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    fn title(&self) -> Option<String> {
+        self.title.clone()
+    }
 }
 
 // implements Node
@@ -469,8 +809,33 @@ pub struct Link {
 // Image can be used where phrasing content is expected. It has no content model, but is described by its alt field.
 // Image includes the mixins Resource and Alternative.
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename = "image")]
 pub struct Image {
     // type: "image"
+    pub url: String,
+    pub title: Option<String>,
+
+    // The url and title as written in the source when they differ from the values, which have backslash
+    // escapes and character references resolved. They are not part of mdast so they are not serialized.
+    #[serde(skip)]
+    pub raw_url: Option<String>,
+    #[serde(skip)]
+    pub raw_title: Option<String>,
+    #[serde(flatten)]
+    pub alternative: Alternative,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub position: Option<Position>,
+}
+
+impl Resource for Image {
+    fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    fn title(&self) -> Option<String> {
+        self.title.clone()
+    }
 }
 
 // implements Parent
@@ -479,11 +844,19 @@ pub struct Image {
 // LinkReference includes the mixin Reference.
 // LinkReferences should be associated with a Definition.
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename = "linkReference")]
 pub struct LinkReference {
     // type: "linkReference"
+    #[serde(flatten)]
+    pub reference: Reference,
+    #[serde(flatten)]
+    pub association: Association,
 
     // children: [StaticPhrasingContent]
-    children: Vec<StaticPhrasingContent>,
+    pub children: Vec<StaticPhrasingContent>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub position: Option<Position>,
 }
 
 // implements Node
@@ -492,8 +865,18 @@ pub struct LinkReference {
 // ImageReference includes the mixins Reference and Alternative.
 // ImageReference should be associated with a Definition.
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename = "imageReference")]
 pub struct ImageReference {
     // type: "imageReference"
+    #[serde(flatten)]
+    pub reference: Reference,
+    #[serde(flatten)]
+    pub association: Association,
+    #[serde(flatten)]
+    pub alternative: Alternative,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub position: Option<Position>,
 }
 
 // ------ MIXINS ------
@@ -511,23 +894,47 @@ pub trait Resource {
 // To normalize a value, collapse markdown whitespace ([\t\n\r ]+) to a space, trim the optional initial and/or final space, and perform case-folding.
 // Whether the value of identifier (or normalized label if there is no identifier) is expected to be a unique identifier or not depends on the type of node including the Association.
 // An example of this is that they should be unique on Definition, whereas multiple LinkReferences can be non-unique to be associated with one definition.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Association {
     // An identifier field must be present. It can match another node.
     // identifier is a source value: character escapes and character references are not parsed. Its value must be normalized.
-    identifier: String,
+    pub identifier: String,
 
     // A label field can be present.
     // label is a string value: it works just like title on a link or a lang on code: character escapes and character references are parsed.
-    label: Option<String>,
+    pub label: Option<String>,
+}
+
+impl Association {
+    /// Creates an association from a label as found in the source, normalizing it into the identifier.
+    pub fn new(label: &str) -> Association {
+        Association {
+            identifier: Association::normalize(label),
+            label: Some(label.to_string()),
+        }
+    }
+
+    /// Normalizes a value so that equivalent labels compare equal.
+    /// Case-folding goes through upper case as well so that e.g. "ẞ" matches "SS".
+    pub fn normalize(value: &str) -> String {
+        value
+            .split(['\t', '\n', '\r', ' '])
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase()
+            .to_uppercase()
+            .to_lowercase()
+    }
 }
 
 // Reference represents a marker that is associated to another node.
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
 pub struct Reference {
     // A referenceType field must be present.
     // Its value must be a referenceType. It represents the explicitness of the reference.
-    reference_type: String,
+    pub reference_type: ReferenceType,
 }
 
 // Alternative represents a node with a fallback
@@ -535,13 +942,14 @@ pub struct Reference {
 pub struct Alternative {
     // An alt field should be present.
     // It represents equivalent content for environments that cannot represent the node as intended.
-    alt: Option<String>,
+    pub alt: Option<String>,
 }
 
 // ------ ENUMERATION ------
 
 /// Represents the explicitness of a reference.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
 pub enum ReferenceType {
     /// shortcut: the reference is implicit, its identifier inferred from its content
     Shortcut,
@@ -562,11 +970,14 @@ pub enum ReferenceType {
 //     list_content: ListContent,
 //     phrasing_content: PhrasingContent
 // }
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
 pub enum MdastContent {
     FlowContent(FlowContent),
     ListContent(ListContent),
     PhrasingContent(PhrasingContent),
+    TableContent(TableContent),
+    RowContent(RowContent),
 }
 
 // another possibility is to use trait
@@ -575,6 +986,8 @@ pub enum MdastContent {
 
 // type FlowContent = Blockquote | Code | Heading | HTML | List | ThematicBreak | Content
 // Flow content represent the sections of document.
+// GFM adds Table, which is only produced when tables are enabled in the parser options,
+// and footnotes add FootnoteDefinition.
 // union FlowContent {
 //     blockquote: BlockQuote,
 //     code: Code,
@@ -598,7 +1011,8 @@ pub enum MdastContent {
 // pub struct Foo<T: FlowContent> {}
 
 // or maybe yet just use another enum?
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
 pub enum FlowContent {
     BlockQuote(BlockQuote),
     Code(Code),
@@ -607,6 +1021,8 @@ pub enum FlowContent {
     HTML(HTML),
     List(List),
     ThematicBreak(ThematicBreak),
+    Table(Table),
+    FootnoteDefinition(FootnoteDefinition),
 }
 
 // type Content = Definition | Paragraph
@@ -615,7 +1031,8 @@ pub enum FlowContent {
 //     definition: Definition,
 //     paragraph: Paragraph,
 // }
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
 pub enum Content {
     Definition(Definition),
     Paragraph(Paragraph),
@@ -626,7 +1043,8 @@ pub enum Content {
 // union ListContent {
 //     list_item: ListItem;
 // }
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
 pub enum ListContent {
     ListItem(ListItem),
 }
@@ -638,14 +1056,17 @@ pub enum ListContent {
 //     link_reference: LinkReference,
 //     static_phrasing_content: StaticPhrasingContent
 // }
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
 pub enum PhrasingContent {
-    // Link(Link),
-    // LinkReference(LinkReference),
+    Link(Link),
+    LinkReference(LinkReference),
     StaticPhrasingContent(StaticPhrasingContent),
 }
 
 // type StaticPhrasingContent = Break | Emphasis | HTML | Image | ImageReference | InlineCode | Strong | Text
+// GFM adds Delete, which is only produced when strikethrough is enabled in the parser options,
+// and footnotes add Footnote and FootnoteReference.
 // StaticPhrasing content represent the text in a document, and its markup, that is not intended for user interaction.
 // union StaticPhrasingContent {
 //     break: Break,
@@ -659,7 +1080,8 @@ pub enum PhrasingContent {
 // }
 
 // TODO: alias for Vec<StaticPhrasingContent>
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize)]
+#[serde(untagged)]
 pub enum StaticPhrasingContent {
     Break(Break),
     /// aka italic
@@ -672,6 +1094,10 @@ pub enum StaticPhrasingContent {
     Strong(Strong),
     /// aka plaintext
     Text(Text),
+    /// aka strikethrough, from GFM
+    Delete(Delete),
+    Footnote(Footnote),
+    FootnoteReference(FootnoteReference),
 }
 
 // impl fmt::Display for StaticPhrasingContent {
@@ -694,6 +1120,12 @@ impl fmt::Debug for StaticPhrasingContent {
             StaticPhrasingContent::Emphasis(e) => {
                 write!(f, "{:?}", e.children)
             }
+            StaticPhrasingContent::Strong(s) => {
+                write!(f, "{:?}", s.children)
+            }
+            StaticPhrasingContent::Delete(d) => {
+                write!(f, "{:?}", d.children)
+            }
             StaticPhrasingContent::Text(t) => {
                 write!(f, "{:?}", t.value)
             }
@@ -724,50 +1156,88 @@ pub enum TransparentContent {
 // Table (Parent) represents two-dimensional data.
 // Table can be used where flow content is expected. Its content model is table content.
 // The head of the node represents the labels of the columns.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename = "table")]
 pub struct Table {
     // type: "table"
 
     // Represents how cells in columns are aligned.
-    align: Option<Vec<AlignType>>,
+    pub align: Option<Vec<AlignType>>,
+
     // children: [TableContent]
+    pub children: Vec<TableContent>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub position: Option<Position>,
+}
+
+impl Table {
+    /// The row with the labels of the columns.
+    pub fn head(&self) -> Option<&TableRow> {
+        self.children.first().map(|TableContent::TableRow(row)| row)
+    }
+
+    /// The rows after the head.
+    pub fn body(&self) -> impl Iterator<Item = &TableRow> {
+        self.children
+            .iter()
+            .skip(1)
+            .map(|TableContent::TableRow(row)| row)
+    }
 }
 
 // implements Parent
 // TableRow (Parent) represents a row of cells in a table.
 // TableRow can be used where table content is expected. Its content model is row content.
 // If the node is a head, it represents the labels of the columns for its parent Table.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename = "tableRow")]
 pub struct TableRow {
     // type: "tableRow"
-// children: [RowContent]
+
+    // children: [RowContent]
+    pub children: Vec<RowContent>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub position: Option<Position>,
 }
 
 // implements Parent
 // TableCell (Parent) represents a header cell in a Table, if its parent is a head, or a data cell otherwise.
 // TableCell can be used where row content is expected. Its content model is phrasing content excluding Break nodes.
-
+// Cells that pad a row to the width of the table are generated and have no position.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename = "tableCell")]
 pub struct TableCell {
     // type: "tableCell"
-// children: [PhrasingContent]
-}
 
-// implements ListItem
-pub struct ListItemGfm {
-    // In GFM, a checked field can be present.
-    // It represents whether the item is done (when true), not done (when false), or indeterminate or not applicable (when null or not present).
-    checked: Option<bool>,
+    // children: [PhrasingContent]
+    pub children: Vec<PhrasingContent>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub position: Option<Position>,
 }
 
 // implements Parent
 // Delete (Parent) represents contents that are no longer accurate or no longer relevant.
 // Delete can be used where phrasing content is expected. Its content model is transparent content.
 // aka strikethrough
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename = "delete")]
 pub struct Delete {
     // type: "delete"
-// children: [TransparentContent]
+
+    // children: [TransparentContent]
+    pub children: Vec<PhrasingContent>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub position: Option<Position>,
 }
 
 /// Represents how phrasing content is aligned ([CSSTEXT]).
-enum AlignType {
+/// In mdast JSON it is `"left"`, `"right"`, `"center"` or `null` for [`AlignType::None`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlignType {
     // See the left value of the text-align CSS property
     Left,
     // See the right value of the text-align CSS property
@@ -778,24 +1248,15 @@ enum AlignType {
     None,
 }
 
-// type FlowContentGfm = Table | FlowContent
-// union FlowContentGfm {
-//     table: Table,
-//     flow_content: FlowContent
-// }
-
-enum FlowContentGfm {
-    Table(Table),
-    FlowContent(FlowContent),
-}
-
 // type TableContent = TableRow
 // Table content represent the rows in a table.
 // union TableContent {
 //     table_row: TableRow
 // }
 
-enum TableContent {
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+pub enum TableContent {
     TableRow(TableRow),
 }
 
@@ -805,52 +1266,76 @@ enum TableContent {
 //     table_cell: TableCell
 // }
 
-enum RowContent {
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+pub enum RowContent {
     TableCell(TableCell),
 }
 
-// type ListContentGfm = ListItemGfm
-// union ListContentGfm {
-//     list_item_gfm: ListItemGfm
-// }
-
-enum ListContentGfm {
-    ListItemGfm(ListItemGfm),
-}
-
-// type StaticPhrasingContentGfm = Delete | StaticPhrasingContent
-// union StaticPhrasingContentGfm {
-//     delete: Delete,
-//     static_phrasing_content: StaticPhrasingContent
-// }
-
-enum StaticPhrasingContentGfm {
-    Delete(Delete),
-    StaticPhrasingContent(StaticPhrasingContent),
-}
-
 // ### Frontmatter ###
 
 // implements Literal
 // The following interfaces are found with YAML.
 // YAML (Literal) represents a collection of metadata for the document in the YAML ([YAML]) data serialisation language.
 // YAML can be used where frontmatter content is expected. Its content is represented by its value field.
+#[derive(Serialize, Deserialize, Debug)]
+#[allow(clippy::upper_case_acronyms)]
+#[serde(tag = "type", rename = "yaml")]
 pub struct YAML {
     // type: "yaml"
+    /// The YAML between the fences, which can be read with [`YAML::map`].
+    #[serde(with = "utf8")]
+    pub value: Vec<u8>,
 
-    // TODO: I would like this to be a list of key/value pairs
-    value: Vec<u8>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub position: Option<Position>,
 }
 
-// type FrontmatterContent = YAML
+// implements Literal
+// Toml (Literal) represents a collection of metadata for the document in the TOML data serialisation language,
+// as written between `+++` fences by Hugo. It is not part of mdast but follows remark-frontmatter.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename = "toml")]
+pub struct Toml {
+    // type: "toml"
+    /// The TOML between the fences, which can be read with [`Toml::map`].
+    #[serde(with = "utf8")]
+    pub value: Vec<u8>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub position: Option<Position>,
+}
+
+// implements Literal
+// Json (Literal) represents a collection of metadata for the document as a JSON object at its start.
+// It is not part of mdast.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename = "json")]
+pub struct Json {
+    // type: "json"
+    /// The JSON object, braces included, which can be read with [`Json::map`].
+    #[serde(with = "utf8")]
+    pub value: Vec<u8>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub position: Option<Position>,
+}
+
+// type FrontmatterContent = YAML | Toml | Json
 // Frontmatter content represent out-of-band information about the document.
 // If frontmatter is present, it must be limited to one node in the tree, and can only exist as a head.
 // union FrontmatterContent {
 //     yaml: YAML,
+//     toml: Toml,
+//     json: Json,
 // }
 
-enum FrontmatterContent {
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+pub enum FrontmatterContent {
     YAML(YAML),
+    Toml(Toml),
+    Json(Json),
 }
 
 // type FlowContentFrontmatter = FrontmatterContent | FlowContent
@@ -859,7 +1344,9 @@ enum FrontmatterContent {
 //     flow_content: FlowContent
 // }
 
-enum FlowContentFrontmatter {
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+pub enum FlowContentFrontmatter {
     FrontmatterContent(FrontmatterContent),
     FlowContent(FlowContent),
 }
@@ -869,17 +1356,34 @@ enum FlowContentFrontmatter {
 // FootnoteDefinition can be used where flow content is expected. Its content model is also flow content.
 // FootnoteDefinition includes the mixin Association.
 // FootnoteDefinition should be associated with FootnoteReferences.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename = "footnoteDefinition")]
 pub struct FootnoteDefinition {
     // type: "footnoteDefinition"
-// children: [FlowContent]
+    #[serde(flatten)]
+    pub association: Association,
+
+    // children: [FlowContent]
+    pub children: Vec<FlowContent>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub position: Option<Position>,
 }
 
 // implements Parent
 // Footnote (Parent) represents content relating to the document that is outside its flow.
 // Footnote can be used where phrasing content is expected. Its content model is also phrasing content.
+// These are inline footnotes, written as ^[text].
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename = "footnote")]
 pub struct Footnote {
     // type: "footnote"
-// children: [PhrasingContent]
+
+    // children: [PhrasingContent]
+    pub children: Vec<PhrasingContent>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub position: Option<Position>,
 }
 
 // implement Node
@@ -887,30 +1391,13 @@ pub struct Footnote {
 // FootnoteReference can be used where phrasing content is expected. It has no content model.
 // FootnoteReference includes the mixin Association.
 // FootnoteReference should be associated with a FootnoteDefinition.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename = "footnoteReference")]
 pub struct FootnoteReference {
     // type: "footnoteReference"
-}
+    #[serde(flatten)]
+    pub association: Association,
 
-// type FlowContentFootnotes = FootnoteDefinition | FlowContent
-// union FlowContentFootnotes {
-//     footnote_definition: FootnoteDefinition,
-//     flow_content: FlowContent
-// }
-
-enum FlowContentFootnotes {
-    FootnoteDefinition(FootnoteDefinition),
-    FlowContent(FlowContent),
-}
-
-// type StaticPhrasingContentFootnotes = Footnote | FootnoteReference | StaticPhrasingContent
-// union StaticPhrasingContentFootnotes {
-//     footnote: Footnote,
-//     footnote_reference: FootnoteReference,
-//     static_phrasing_content: StaticPhrasingContent
-// }
-
-enum StaticPhrasingContentFootnotes {
-    Footnote(Footnote),
-    FootnoteReference(FootnoteReference),
-    StaticPhrasingContent(StaticPhrasingContent),
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub position: Option<Position>,
 }
@@ -0,0 +1,111 @@
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{line_ending, not_line_ending, space0},
+    combinator::{eof, peek},
+    sequence::{pair, terminated},
+    IResult,
+};
+
+// Front matter is a block of metadata at the very start of a document between two fence lines,
+// which may be followed by spaces or tabs. It is recognized the same way by every parser.
+
+// A fence line, leaving the input at its line ending.
+fn fence<'a>(fence: &'static str, i: &'a str) -> IResult<&'a str, &'a str> {
+    terminated(
+        tag(fence),
+        terminated(space0, peek(alt((line_ending, eof)))),
+    )(i)
+}
+
+fn without_line_ending(i: &str) -> &str {
+    i.strip_suffix('\n')
+        .map(|i| i.strip_suffix('\r').unwrap_or(i))
+        .unwrap_or(i)
+}
+
+// Returns the content between the opening and the closing fence, without the line ending before the
+// closing fence, and leaves the input right after the closing fence.
+fn fenced<'a>(
+    opening: &'static str,
+    closing: &'static str,
+    i: &'a str,
+) -> IResult<&'a str, &'a str> {
+    let (content, _) = terminated(|i| fence(opening, i), line_ending)(i)?;
+    let mut rest = content;
+    loop {
+        if let Ok((after, _)) = fence(closing, rest) {
+            let length = content.len() - rest.len();
+            return Ok((after, without_line_ending(&content[..length])));
+        }
+        // without a closing fence this is not front matter
+        let (next, _) = pair(not_line_ending, line_ending)(rest)?;
+        rest = next;
+    }
+}
+
+/// Parses YAML front matter, fenced by `---` lines.
+pub(crate) fn yaml(i: &str) -> IResult<&str, &str> {
+    fenced("---", "---", i)
+}
+
+/// Parses TOML front matter, fenced by `+++` lines.
+pub(crate) fn toml(i: &str) -> IResult<&str, &str> {
+    fenced("+++", "+++", i)
+}
+
+/// Parses JSON front matter, an object whose opening and closing braces are on lines of their own.
+/// Unlike the other formats the braces are part of the front matter.
+pub(crate) fn json(i: &str) -> IResult<&str, &str> {
+    let (rest, _) = fenced("{", "}", i)?;
+    Ok((rest, i[..i.len() - rest.len()].trim_end()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn yaml_fences() {
+        assert_eq!(yaml("---\nAuthor: Sean\n---"), Ok(("", "Author: Sean")));
+        assert_eq!(
+            yaml("---\r\ntitle: a-b\r\n--- \r\n# body"),
+            Ok(("\r\n# body", "title: a-b"))
+        );
+        assert_eq!(yaml("---\n---\n"), Ok(("\n", "")));
+        // dashes inside the front matter do not close it
+        assert_eq!(
+            yaml("---\nlist:\n- a\n----\n---\n"),
+            Ok(("\n", "list:\n- a\n----"))
+        );
+    }
+
+    #[test]
+    fn not_yaml() {
+        assert!(yaml("---\nAuthor: Sean\n").is_err());
+        assert!(yaml("--- title\n---\n").is_err());
+        assert!(yaml("\n---\na: 1\n---\n").is_err());
+        assert!(yaml("+++\na = 1\n+++\n").is_err());
+    }
+
+    #[test]
+    fn toml_fences() {
+        assert_eq!(
+            toml("+++\ntitle = \"Hello\"\n+++\n# body"),
+            Ok(("\n# body", "title = \"Hello\""))
+        );
+        assert!(toml("---\na: 1\n---\n").is_err());
+    }
+
+    #[test]
+    fn json_braces() {
+        assert_eq!(
+            json("{\n  \"a\": {\n    \"b\": 1\n  }\n} \n# body"),
+            Ok(("\n# body", "{\n  \"a\": {\n    \"b\": 1\n  }\n}"))
+        );
+        assert_eq!(json("{\n}"), Ok(("", "{\n}")));
+        // a paragraph that happens to start with a brace
+        assert!(json("{{< figure >}}\n").is_err());
+        assert!(json("{ \"a\": 1 }\n").is_err());
+    }
+}
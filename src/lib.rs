@@ -1,3 +1,4 @@
 mod asciidoc;
-mod ast;
-mod markdown;
+pub mod ast;
+mod front_matter;
+pub mod markdown;
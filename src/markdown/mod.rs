@@ -1 +1,89 @@
+use std::error::Error;
+use std::fmt;
+
 mod parser;
+
+pub(crate) use parser::{footnote_label, unescape_with_offsets};
+pub use parser::{parse, parse_with_options};
+
+/// Options controlling which syntax beyond CommonMark the parser recognizes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    /// Parse a frontmatter block at the start of the document into the head of the [`Root`](crate::ast::Root).
+    pub front_matter: bool,
+    /// Parse GFM tables into [`Table`](crate::ast::Table) nodes.
+    pub tables: bool,
+    /// Parse GFM task list markers (`[ ]` or `[x]`) at the start of list items into their `checked` field.
+    pub task_lists: bool,
+    /// Parse GFM strikethrough (`~~text~~`) into [`Delete`](crate::ast::Delete) nodes.
+    pub strikethrough: bool,
+    /// Also accept strikethrough with a single tilde on either side (`~text~`).
+    pub single_tilde: bool,
+    /// Parse footnote definitions (`[^id]: text`), references (`[^id]`) and inline footnotes (`^[text]`).
+    pub footnotes: bool,
+    /// Parse GFM extended autolinks: `www.` and `http(s)://` URLs and email addresses written without angle brackets.
+    pub autolink_literals: bool,
+}
+
+impl Options {
+    /// Strict CommonMark, with every extension disabled.
+    pub fn commonmark() -> Options {
+        Options {
+            front_matter: false,
+            tables: false,
+            task_lists: false,
+            strikethrough: false,
+            single_tilde: false,
+            footnotes: false,
+            autolink_literals: false,
+        }
+    }
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            front_matter: true,
+            tables: true,
+            task_lists: true,
+            strikethrough: true,
+            single_tilde: true,
+            footnotes: true,
+            autolink_literals: true,
+        }
+    }
+}
+
+/// Error returned when a markdown document could not be parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    message: String,
+    offset: usize,
+}
+
+impl ParseError {
+    pub fn new(message: impl Into<String>, offset: usize) -> ParseError {
+        ParseError {
+            message: message.into(),
+            offset,
+        }
+    }
+
+    /// A description of what went wrong.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The byte offset in the source at which parsing failed.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at offset {}", self.message, self.offset)
+    }
+}
+
+impl Error for ParseError {}
@@ -0,0 +1,205 @@
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take_while, take_while1, take_while_m_n},
+    character::complete::char,
+    combinator::{recognize, verify},
+    error::{Error, ErrorKind},
+    multi::separated_list1,
+    sequence::{delimited, pair, preceded, separated_pair, tuple},
+    IResult,
+};
+
+// CommonMark autolinks are absolute URIs and email addresses inside `<` and `>`.
+// https://spec.commonmark.org/0.30/#autolinks
+// GFM also recognizes some URLs and email addresses without the brackets, extended autolinks.
+// https://github.github.com/gfm/#autolinks-extension-
+
+fn error(i: &str, kind: ErrorKind) -> nom::Err<Error<&str>> {
+    nom::Err::Error(Error::new(i, kind))
+}
+
+// A scheme is any sequence of 2–32 characters beginning with an ASCII letter and followed by any combination
+// of ASCII letters, digits, or the symbols plus (”+”), period (”.”), or hyphen (”-”).
+fn scheme(i: &str) -> IResult<&str, &str> {
+    recognize(pair(
+        take_while_m_n(1, 1, |c: char| c.is_ascii_alphabetic()),
+        take_while_m_n(1, 31, |c: char| {
+            c.is_ascii_alphanumeric() || matches!(c, '+' | '.' | '-')
+        }),
+    ))(i)
+}
+
+// An absolute URI consists of a scheme followed by a colon (:) followed by zero or more characters other than
+// ASCII control characters, space, <, and >.
+fn absolute_uri(i: &str) -> IResult<&str, &str> {
+    recognize(separated_pair(
+        scheme,
+        char(':'),
+        take_while(|c: char| !c.is_ascii_control() && !matches!(c, ' ' | '<' | '>')),
+    ))(i)
+}
+
+// The email address grammar of the HTML spec: a local part of letters, digits and .!#$%&'*+/=?^_`{|}~-
+// followed by @ and labels of at most 63 letters, digits or hyphens that neither start nor end with a hyphen.
+fn email_address(i: &str) -> IResult<&str, &str> {
+    let local =
+        take_while1(|c: char| c.is_ascii_alphanumeric() || ".!#$%&'*+/=?^_`{|}~-".contains(c));
+    let label = verify(
+        take_while_m_n(1, 63, |c: char| c.is_ascii_alphanumeric() || c == '-'),
+        |label: &str| !label.starts_with('-') && !label.ends_with('-'),
+    );
+    recognize(separated_pair(
+        local,
+        char('@'),
+        separated_list1(char('.'), label),
+    ))(i)
+}
+
+// Returns the URI between the brackets.
+pub(crate) fn uri_autolink(i: &str) -> IResult<&str, &str> {
+    delimited(char('<'), absolute_uri, char('>'))(i)
+}
+
+// Returns the email address between the brackets.
+pub(crate) fn email_autolink(i: &str) -> IResult<&str, &str> {
+    delimited(char('<'), email_address, char('>'))(i)
+}
+
+// A valid domain consists of segments of alphanumeric characters, underscores (_) and hyphens (-) separated by
+// periods (.). There must be at least one period, and no underscores may be present in the last two segments.
+fn valid_domain(i: &str) -> IResult<&str, &str> {
+    verify(
+        take_while1(|c: char| c.is_alphanumeric() || matches!(c, '_' | '-' | '.')),
+        |domain: &str| {
+            domain.contains('.')
+                && domain
+                    .trim_end_matches('.')
+                    .rsplit('.')
+                    .take(2)
+                    .all(|segment| !segment.contains('_'))
+        },
+    )(i)
+}
+
+// Trailing punctuation is not part of an extended autolink. A trailing ) only is when it has a matching (
+// in the link, and an entity-like `&name;` at the end is left out along with its ampersand.
+fn trim_trailing_punctuation(link: &str) -> &str {
+    let mut link = link;
+    loop {
+        match link.chars().next_back() {
+            Some('?' | '!' | '.' | ',' | ':' | '*' | '_' | '~') => link = &link[..link.len() - 1],
+            Some(')') if link.matches(')').count() > link.matches('(').count() => {
+                link = &link[..link.len() - 1]
+            }
+            Some(';') => {
+                let name =
+                    link[..link.len() - 1].trim_end_matches(|c: char| c.is_ascii_alphanumeric());
+                link = match name.strip_suffix('&') {
+                    Some(before) if name.len() < link.len() - 1 => before,
+                    _ => &link[..link.len() - 1],
+                };
+            }
+            _ => return link,
+        }
+    }
+}
+
+// A domain followed by a path of anything but whitespace and `<`, with trailing punctuation trimmed.
+fn domain_and_path(i: &str) -> IResult<&str, &str> {
+    let (_, link) = recognize(pair(
+        valid_domain,
+        take_while(|c: char| !c.is_whitespace() && c != '<'),
+    ))(i)?;
+    let link = trim_trailing_punctuation(link);
+    Ok((&i[link.len()..], link))
+}
+
+// An extended www autolink is `www.` followed by a valid domain and an optional path.
+// Returns the link as written, which is shown as its text and gets `http://` prepended for its url.
+pub(crate) fn www_autolink(i: &str) -> IResult<&str, &str> {
+    let (rest, link) = domain_and_path(i)?;
+    if !link.starts_with("www.") || link.len() == "www.".len() {
+        return Err(error(i, ErrorKind::Verify));
+    }
+    Ok((rest, link))
+}
+
+// An extended url autolink is `http://` or `https://` followed by a valid domain and an optional path.
+pub(crate) fn url_autolink(i: &str) -> IResult<&str, &str> {
+    let (rest, _) = preceded(alt((tag("http://"), tag("https://"))), domain_and_path)(i)?;
+    Ok((rest, &i[..i.len() - rest.len()]))
+}
+
+// An extended email autolink is a local part of alphanumeric characters, `.`, `-`, `_` or `+`, an `@` and
+// a domain of alphanumeric characters, `-` or `_` separated by periods. The domain needs at least one period
+// and may not end with `-` or `_`, while a trailing period is left out of the link.
+pub(crate) fn email_autolink_literal(i: &str) -> IResult<&str, &str> {
+    let (_, (local, _, domain)) = tuple((
+        take_while1(is_email_local),
+        char('@'),
+        take_while1(|c: char| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')),
+    ))(i)?;
+    let domain = domain.trim_end_matches('.');
+    if !domain.contains('.') || domain.ends_with(['-', '_']) {
+        return Err(error(i, ErrorKind::Verify));
+    }
+    let len = local.len() + 1 + domain.len();
+    Ok((&i[len..], &i[..len]))
+}
+
+/// Whether the character may be part of the local part of an extended email autolink.
+pub(crate) fn is_email_local(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | '+')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn autolinks() {
+        assert_eq!(
+            uri_autolink("<http://foo.bar.baz/test?q=hello&id=22&boolean> x"),
+            Ok((" x", "http://foo.bar.baz/test?q=hello&id=22&boolean"))
+        );
+        assert_eq!(uri_autolink("<a+b+c:d>"), Ok(("", "a+b+c:d")));
+        assert!(uri_autolink("<m:abc>").is_err());
+        assert!(uri_autolink("<http://foo.bar/baz bim>").is_err());
+        assert_eq!(
+            email_autolink("<foo+special@Bar.baz-bar0.com>"),
+            Ok(("", "foo+special@Bar.baz-bar0.com"))
+        );
+        assert!(email_autolink("<foo\\+@bar.example.com>").is_err());
+        assert!(email_autolink("<foo@-bar.com>").is_err());
+    }
+
+    #[test]
+    fn extended_autolinks() {
+        assert_eq!(
+            www_autolink("www.commonmark.org/help."),
+            Ok((".", "www.commonmark.org/help"))
+        );
+        assert!(www_autolink("www.").is_err());
+        assert!(www_autolink("www.a_b.c_d").is_err());
+        assert_eq!(
+            url_autolink("https://en.wikipedia.org/wiki/Markdown_(syntax)), x"),
+            Ok(("), x", "https://en.wikipedia.org/wiki/Markdown_(syntax)"))
+        );
+        assert_eq!(
+            www_autolink("www.google.com/search?q=commonmark&hl;"),
+            Ok(("&hl;", "www.google.com/search?q=commonmark"))
+        );
+        assert_eq!(
+            www_autolink("www.google.com/search?q=commonmark;"),
+            Ok((";", "www.google.com/search?q=commonmark"))
+        );
+        assert_eq!(www_autolink("www.a.b/c<d"), Ok(("<d", "www.a.b/c")));
+        assert!(url_autolink("ftp://a.b").is_err());
+        assert_eq!(
+            email_autolink_literal("a.b-c_d@a.b."),
+            Ok((".", "a.b-c_d@a.b"))
+        );
+        assert!(email_autolink_literal("a.b-c_d@a.b-").is_err());
+        assert!(email_autolink_literal("hello@mail+xyz.example").is_err());
+    }
+}
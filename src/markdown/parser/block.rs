@@ -0,0 +1,1411 @@
+use super::entity::{unescape_raw, unescape_raw_option};
+use super::html::{html_block_end, html_block_start};
+use super::inline::{parse_inlines, Definitions};
+use super::link::{footnote_label, link_reference_definition};
+use super::position::{LineIndex, Locator, SourceMap};
+use crate::ast::{
+    AlignType, Association, BlockQuote, Code, Content, Definition, FlowContent, FootnoteDefinition,
+    Heading, List, ListContent, ListItem, Paragraph, PhrasingContent, Position, RowContent, Table,
+    TableCell, TableContent, TableRow, ThematicBreak, HTML,
+};
+use crate::markdown::Options;
+use nom::{
+    branch::alt,
+    bytes::complete::{take_while1, take_while_m_n},
+    character::complete::{char, none_of, one_of, space0, space1},
+    combinator::{eof, map, peek, recognize, verify},
+    multi::many1,
+    sequence::{delimited, pair, terminated},
+    IResult,
+};
+
+// The block structure phase of the parsing strategy described in
+// https://spec.commonmark.org/0.30/#appendix-a-parsing-strategy
+// Lines are consumed one at a time and used to build a tree of container and leaf blocks.
+// Once the document has been read the raw text content of paragraphs and headings is handed to
+// the inline parser while the tree is converted to mdast flow content.
+
+const CODE_INDENT: usize = 4;
+
+// Index of the document block in the arena.
+const DOCUMENT: usize = 0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ListData {
+    ordered: bool,
+    // the bullet character for unordered lists or the delimiter (. or )) for ordered lists
+    marker: char,
+    start: u64,
+    padding: usize,
+    marker_offset: usize,
+}
+
+impl ListData {
+    // Two list items are of the same type if they begin with the same bullet character
+    // or if they have the same delimiter.
+    fn matches(&self, other: &ListData) -> bool {
+        self.ordered == other.ordered && self.marker == other.marker
+    }
+}
+
+#[derive(Debug)]
+enum BlockKind {
+    Document,
+    BlockQuote,
+    List(ListData),
+    Item(ListData),
+    Paragraph,
+    Heading {
+        depth: usize,
+        setext: bool,
+    },
+    ThematicBreak,
+    IndentedCode,
+    FencedCode {
+        fence_char: char,
+        fence_length: usize,
+        fence_offset: usize,
+    },
+    Html(u8),
+    Definition,
+    // the alignment of each column, taken from the delimiter row
+    Table(Vec<AlignType>),
+    // the label of the footnote
+    FootnoteDefinition(String),
+}
+
+impl BlockKind {
+    fn accepts_lines(&self) -> bool {
+        matches!(
+            self,
+            BlockKind::Paragraph
+                | BlockKind::IndentedCode
+                | BlockKind::FencedCode { .. }
+                | BlockKind::Html(_)
+                | BlockKind::Table(_)
+        )
+    }
+
+    fn can_contain(&self, child: &BlockKind) -> bool {
+        match self {
+            BlockKind::Document
+            | BlockKind::BlockQuote
+            | BlockKind::Item(_)
+            | BlockKind::FootnoteDefinition(_) => !matches!(child, BlockKind::Item(_)),
+            BlockKind::List(_) => matches!(child, BlockKind::Item(_)),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Block {
+    kind: BlockKind,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    open: bool,
+    content: String,
+    // where the content came from in the source
+    map: SourceMap,
+    // source offsets of the first character and right after the last non-whitespace character
+    start: usize,
+    end: usize,
+}
+
+enum Continuation {
+    Matched,
+    NotMatched,
+    // the line was fully consumed, e.g. by a closing code fence
+    LineDone,
+}
+
+enum Start {
+    None,
+    Container,
+    Leaf,
+}
+
+// ---- line level recognizers ----
+
+fn is_space_or_tab(c: Option<u8>) -> bool {
+    matches!(c, Some(b' ') | Some(b'\t'))
+}
+
+// An opening sequence of 1–6 unescaped # characters followed by spaces or tabs or the end of line.
+fn atx_heading_start(i: &str) -> IResult<&str, usize> {
+    map(
+        terminated(take_while_m_n(1, 6, |c| c == '#'), alt((space1, eof))),
+        |hashes: &str| hashes.len(),
+    )(i)
+}
+
+// A sequence of at least three consecutive backtick characters (`) or tildes (~).
+// If the info string comes after a backtick fence, it may not contain any backtick characters.
+fn code_fence(i: &str) -> IResult<&str, &str> {
+    alt((
+        verify(take_while1(|c| c == '`'), |fence: &str| {
+            fence.len() >= 3 && !i[fence.len()..].contains('`')
+        }),
+        verify(take_while1(|c| c == '~'), |fence: &str| fence.len() >= 3),
+    ))(i)
+}
+
+// Like remark, the info string is split at its first run of spaces or tabs into the language and the meta
+// data, which are both absent when empty. Escapes and references are resolved after splitting.
+fn info_string(info: &str) -> (Option<&str>, Option<&str>) {
+    let info = info.trim_matches([' ', '\t']);
+    let mut parts = info.splitn(2, [' ', '\t']);
+    let lang = parts.next().filter(|lang| !lang.is_empty());
+    let meta = parts
+        .next()
+        .map(|meta| meta.trim_matches([' ', '\t']))
+        .filter(|meta| !meta.is_empty());
+    (lang, meta)
+}
+
+// The closing code fence may be preceded by up to three spaces of indentation,
+// and may be followed only by spaces or tabs.
+fn closing_code_fence(i: &str) -> IResult<&str, &str> {
+    terminated(
+        alt((take_while1(|c| c == '`'), take_while1(|c| c == '~'))),
+        pair(space0, eof),
+    )(i)
+}
+
+// A setext heading underline is a sequence of = characters or a sequence of - characters,
+// with no more than 3 spaces of indentation and any number of trailing spaces or tabs.
+fn setext_heading_underline(i: &str) -> IResult<&str, usize> {
+    map(
+        terminated(
+            alt((take_while1(|c| c == '='), take_while1(|c| c == '-'))),
+            pair(space0, eof),
+        ),
+        |underline: &str| if underline.starts_with('=') { 1 } else { 2 },
+    )(i)
+}
+
+// A line consisting of optionally up to three spaces of indentation, followed by a sequence of three or more
+// matching -, _, or * characters, each followed optionally by any number of spaces or tabs, forms a thematic break.
+fn thematic_break(i: &str) -> IResult<&str, &str> {
+    let marker = match i.chars().next() {
+        Some(c @ '-') | Some(c @ '_') | Some(c @ '*') => c,
+        _ => {
+            return Err(nom::Err::Error(nom::error::Error::new(
+                i,
+                nom::error::ErrorKind::OneOf,
+            )))
+        }
+    };
+    recognize(terminated(
+        verify(many1(pair(char(marker), space0)), |markers: &Vec<_>| {
+            markers.len() >= 3
+        }),
+        eof,
+    ))(i)
+}
+
+// A bullet list marker is a -, +, or * character.
+// An ordered list marker is a sequence of 1–9 arabic digits (0-9), followed by either a . character or a ) character.
+fn list_marker(i: &str) -> IResult<&str, (bool, char, u64)> {
+    alt((
+        map(one_of("-+*"), |bullet| (false, bullet, 1)),
+        map(
+            pair(
+                take_while_m_n(1, 9, |c: char| c.is_ascii_digit()),
+                one_of(".)"),
+            ),
+            |(digits, delimiter): (&str, char)| (true, delimiter, digits.parse().unwrap_or(0)),
+        ),
+    ))(i)
+}
+
+// A footnote definition starts with a footnote label followed by a colon. Spaces or tabs after the colon
+// are skipped so the content may start at any column.
+fn footnote_definition_start(i: &str) -> IResult<&str, &str> {
+    terminated(footnote_label, pair(char(':'), space0))(i)
+}
+
+// A task list item marker is a space, tab or x in either case between brackets, followed by spaces or tabs
+// and the text of the task on the same line.
+fn task_list_marker(i: &str) -> IResult<&str, bool> {
+    terminated(
+        map(delimited(char('['), one_of(" \txX"), char(']')), |c| {
+            c == 'x' || c == 'X'
+        }),
+        terminated(space1, peek(none_of("\n"))),
+    )(i)
+}
+
+// Characters that can begin a block other than a paragraph.
+fn maybe_special(c: Option<u8>) -> bool {
+    match c {
+        Some(c) => b"#`~*+_=<>-|:[".contains(&c) || c.is_ascii_digit(),
+        None => false,
+    }
+}
+
+// Whether the byte at `i` is escaped, which it is after an odd number of backslashes as a backslash
+// only escapes the character right after it.
+fn is_escaped(bytes: &[u8], i: usize) -> bool {
+    bytes[..i].iter().rev().take_while(|&&b| b == b'\\').count() % 2 == 1
+}
+
+// Splits a table row into the ranges of its cells, leaving out the pipes between them
+// and a leading and trailing pipe. A pipe escaped with a backslash is part of the cell.
+fn table_cells(line: &str) -> Vec<(usize, usize)> {
+    let bytes = line.as_bytes();
+    let is_pipe = |i: usize| bytes[i] == b'|' && !is_escaped(bytes, i);
+    let mut start = line.len() - line.trim_start_matches([' ', '\t']).len();
+    let mut end = line.trim_end_matches([' ', '\t']).len();
+    if start < end && is_pipe(start) {
+        start += 1;
+    }
+    if start < end && is_pipe(end - 1) {
+        end -= 1;
+    }
+    let mut cells = Vec::new();
+    let mut cell = start;
+    for i in start..end {
+        if is_pipe(i) {
+            cells.push((cell, i));
+            cell = i + 1;
+        }
+    }
+    cells.push((cell, end));
+    cells
+}
+
+// The delimiter row of a table has a cell of hyphens for each column, with an optional colon on either side
+// that tells the alignment. It needs at least one pipe so a line of hyphens can remain a setext underline.
+fn table_delimiter_row(line: &str) -> Option<Vec<AlignType>> {
+    if !line.contains('|') {
+        return None;
+    }
+    table_cells(line)
+        .into_iter()
+        .map(|(start, end)| {
+            let cell = line[start..end].trim_matches([' ', '\t']);
+            let hyphens = cell.strip_prefix(':').unwrap_or(cell);
+            let hyphens = hyphens.strip_suffix(':').unwrap_or(hyphens);
+            if hyphens.is_empty() || !hyphens.bytes().all(|c| c == b'-') {
+                return None;
+            }
+            Some(match (cell.starts_with(':'), cell.ends_with(':')) {
+                (true, true) => AlignType::Center,
+                (true, false) => AlignType::Left,
+                (false, true) => AlignType::Right,
+                (false, false) => AlignType::None,
+            })
+        })
+        .collect()
+}
+
+fn is_blank(s: &str) -> bool {
+    s.chars().all(|c| matches!(c, ' ' | '\t' | '\n' | '\r'))
+}
+
+// Splits the input on line endings (\n, \r\n or \r) into lines along with their offset in the input.
+// A final line ending does not start a new line.
+fn lines(input: &str) -> Vec<(usize, &str)> {
+    let mut lines = Vec::new();
+    let bytes = input.as_bytes();
+    let mut start = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\n' => {
+                lines.push((start, &input[start..i]));
+                start = i + 1;
+            }
+            b'\r' => {
+                lines.push((start, &input[start..i]));
+                if bytes.get(i + 1) == Some(&b'\n') {
+                    i += 1;
+                }
+                start = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    if start < input.len() {
+        lines.push((start, &input[start..]));
+    }
+    lines
+}
+
+pub(crate) struct BlockParser<'a> {
+    source: &'a LineIndex<'a>,
+    options: &'a Options,
+    blocks: Vec<Block>,
+    tip: usize,
+    old_tip: usize,
+    last_matched_container: usize,
+    all_closed: bool,
+    line: &'a str,
+    // offset of the current line in the source
+    line_start: usize,
+    offset: usize,
+    column: usize,
+    next_nonspace: usize,
+    next_nonspace_column: usize,
+    indent: usize,
+    indented: bool,
+    blank: bool,
+    partially_consumed_tab: bool,
+    // labels of the link reference and footnote definitions found so far
+    definitions: Definitions,
+}
+
+impl<'a> BlockParser<'a> {
+    pub(crate) fn new(source: &'a LineIndex<'a>, options: &'a Options) -> BlockParser<'a> {
+        BlockParser {
+            source,
+            options,
+            blocks: vec![Block {
+                kind: BlockKind::Document,
+                parent: None,
+                children: Vec::new(),
+                open: true,
+                content: String::new(),
+                map: SourceMap::default(),
+                start: 0,
+                end: 0,
+            }],
+            tip: DOCUMENT,
+            old_tip: DOCUMENT,
+            last_matched_container: DOCUMENT,
+            all_closed: true,
+            line: "",
+            line_start: 0,
+            offset: 0,
+            column: 0,
+            next_nonspace: 0,
+            next_nonspace_column: 0,
+            indent: 0,
+            indented: false,
+            blank: false,
+            partially_consumed_tab: false,
+            definitions: Definitions::default(),
+        }
+    }
+
+    /// Parses the input, starting at byte offset `start`, into flow content.
+    pub(crate) fn parse(mut self, input: &'a str, start: usize) -> Vec<FlowContent> {
+        for (offset, line) in lines(&input[start..]) {
+            self.line_start = start + offset;
+            self.incorporate_line(line);
+        }
+        loop {
+            let tip = self.tip;
+            self.finalize(tip);
+            if tip == DOCUMENT {
+                break;
+            }
+        }
+        self.blocks[DOCUMENT]
+            .children
+            .iter()
+            .map(|&child| self.flow_content(child))
+            .collect()
+    }
+
+    fn peek(&self, pos: usize) -> Option<u8> {
+        self.line.as_bytes().get(pos).copied()
+    }
+
+    fn rest(&self, pos: usize) -> &'a str {
+        &self.line[pos.min(self.line.len())..]
+    }
+
+    fn find_next_nonspace(&mut self) {
+        let bytes = self.line.as_bytes();
+        let mut i = self.offset;
+        let mut cols = self.column;
+        while let Some(&c) = bytes.get(i) {
+            match c {
+                b' ' => {
+                    i += 1;
+                    cols += 1;
+                }
+                b'\t' => {
+                    i += 1;
+                    cols += 4 - (cols % 4);
+                }
+                _ => break,
+            }
+        }
+        self.blank = i >= bytes.len();
+        self.next_nonspace = i;
+        self.next_nonspace_column = cols;
+        self.indent = self.next_nonspace_column - self.column;
+        self.indented = self.indent >= CODE_INDENT;
+    }
+
+    fn advance_next_nonspace(&mut self) {
+        self.offset = self.next_nonspace;
+        self.column = self.next_nonspace_column;
+        self.partially_consumed_tab = false;
+    }
+
+    // Advances the offset by `count` characters, or by `count` columns when a tab only partially counts
+    // towards the total and `columns` is set.
+    fn advance_offset(&mut self, count: usize, columns: bool) {
+        let mut count = count;
+        while count > 0 {
+            let c = match self.rest(self.offset).chars().next() {
+                Some(c) => c,
+                None => break,
+            };
+            if c == '\t' {
+                let chars_to_tab = 4 - (self.column % 4);
+                if columns {
+                    self.partially_consumed_tab = chars_to_tab > count;
+                    let chars_to_advance = chars_to_tab.min(count);
+                    self.column += chars_to_advance;
+                    if !self.partially_consumed_tab {
+                        self.offset += 1;
+                    }
+                    count -= chars_to_advance;
+                } else {
+                    self.partially_consumed_tab = false;
+                    self.column += chars_to_tab;
+                    self.offset += 1;
+                    count -= 1;
+                }
+            } else {
+                self.partially_consumed_tab = false;
+                self.offset += c.len_utf8();
+                self.column += 1;
+                count -= 1;
+            }
+        }
+    }
+
+    fn add_line(&mut self) {
+        let tip = &mut self.blocks[self.tip];
+        if self.partially_consumed_tab {
+            // skip over the tab and add the columns it still stands for as spaces
+            tip.map
+                .push(tip.content.len(), self.line_start + self.offset);
+            self.offset += 1;
+            let chars_to_tab = 4 - (self.column % 4);
+            tip.content.push_str(&" ".repeat(chars_to_tab));
+        }
+        let rest = &self.line[self.offset.min(self.line.len())..];
+        tip.map
+            .push(tip.content.len(), self.line_start + self.offset);
+        tip.content.push_str(rest);
+        tip.content.push('\n');
+    }
+
+    // offset right after the last character of the current line that is not a space or tab
+    fn line_end(&self) -> usize {
+        self.line_start + self.line.trim_end_matches([' ', '\t']).len()
+    }
+
+    // Extends a block and its ancestors to the end of the current line, unless the line is blank.
+    fn extend_to_line_end(&mut self, block: usize) {
+        if is_blank(self.line) {
+            return;
+        }
+        let end = self.line_end();
+        let mut block = Some(block);
+        while let Some(id) = block {
+            self.blocks[id].end = self.blocks[id].end.max(end);
+            block = self.blocks[id].parent;
+        }
+    }
+
+    // Adds a block starting at `offset` in the current line.
+    fn add_child(&mut self, kind: BlockKind, offset: usize) -> usize {
+        while !self.blocks[self.tip].kind.can_contain(&kind) {
+            let tip = self.tip;
+            self.finalize(tip);
+        }
+        let id = self.blocks.len();
+        self.blocks.push(Block {
+            kind,
+            parent: Some(self.tip),
+            children: Vec::new(),
+            open: true,
+            content: String::new(),
+            map: SourceMap::default(),
+            start: self.line_start + offset,
+            end: self.line_end(),
+        });
+        let tip = self.tip;
+        self.blocks[tip].children.push(id);
+        self.tip = id;
+        id
+    }
+
+    fn unlink(&mut self, id: usize) {
+        if let Some(parent) = self.blocks[id].parent {
+            self.blocks[parent].children.retain(|&child| child != id);
+        }
+    }
+
+    fn close_unmatched_blocks(&mut self) {
+        if !self.all_closed {
+            while self.old_tip != self.last_matched_container {
+                let parent = self.blocks[self.old_tip].parent.unwrap_or(DOCUMENT);
+                let old_tip = self.old_tip;
+                self.finalize(old_tip);
+                self.old_tip = parent;
+            }
+            self.all_closed = true;
+        }
+    }
+
+    fn incorporate_line(&mut self, line: &'a str) {
+        let mut container = DOCUMENT;
+        self.old_tip = self.tip;
+        self.offset = 0;
+        self.column = 0;
+        self.blank = false;
+        self.partially_consumed_tab = false;
+        self.line = line;
+
+        // For each open block check whether the line satisfies its continuation condition.
+        while let Some(&last_child) = self.blocks[container].children.last() {
+            if !self.blocks[last_child].open {
+                break;
+            }
+            container = last_child;
+            self.find_next_nonspace();
+            match self.continue_block(container) {
+                Continuation::Matched => {}
+                Continuation::NotMatched => {
+                    container = self.blocks[container].parent.unwrap_or(DOCUMENT);
+                    break;
+                }
+                Continuation::LineDone => {
+                    let tip = self.tip;
+                    self.extend_to_line_end(tip);
+                    return;
+                }
+            }
+        }
+
+        self.all_closed = container == self.old_tip;
+        self.last_matched_container = container;
+
+        // Look for new container and leaf block starts. Like paragraphs, tables end where another block starts.
+        let mut matched_leaf = !matches!(
+            self.blocks[container].kind,
+            BlockKind::Paragraph | BlockKind::Table(_)
+        ) && self.blocks[container].kind.accepts_lines();
+        while !matched_leaf {
+            self.find_next_nonspace();
+
+            if !self.indented && !maybe_special(self.peek(self.next_nonspace)) {
+                self.advance_next_nonspace();
+                break;
+            }
+
+            match self.try_block_starts(container) {
+                Start::Container => container = self.tip,
+                Start::Leaf => {
+                    container = self.tip;
+                    matched_leaf = true;
+                }
+                Start::None => {
+                    self.advance_next_nonspace();
+                    break;
+                }
+            }
+        }
+
+        // What remains at the offset is a text line. First check for a lazy paragraph continuation.
+        if !self.all_closed
+            && !self.blank
+            && matches!(self.blocks[self.tip].kind, BlockKind::Paragraph)
+        {
+            self.add_line();
+        } else {
+            self.close_unmatched_blocks();
+
+            if self.blocks[container].kind.accepts_lines() {
+                self.add_line();
+                if let BlockKind::Html(condition) = self.blocks[container].kind {
+                    if html_block_end(condition, self.rest(self.offset)) {
+                        self.extend_to_line_end(container);
+                        self.finalize(container);
+                    }
+                }
+            } else if self.offset < self.line.len() && !self.blank {
+                self.add_child(BlockKind::Paragraph, self.next_nonspace);
+                self.advance_next_nonspace();
+                self.add_line();
+            }
+        }
+
+        let tip = self.tip;
+        self.extend_to_line_end(tip);
+    }
+
+    fn continue_block(&mut self, container: usize) -> Continuation {
+        match self.blocks[container].kind {
+            BlockKind::Document | BlockKind::List(_) => Continuation::Matched,
+            BlockKind::BlockQuote => {
+                if !self.indented && self.peek(self.next_nonspace) == Some(b'>') {
+                    self.advance_next_nonspace();
+                    self.advance_offset(1, false);
+                    if is_space_or_tab(self.peek(self.offset)) {
+                        self.advance_offset(1, true);
+                    }
+                    Continuation::Matched
+                } else {
+                    Continuation::NotMatched
+                }
+            }
+            BlockKind::Item(data) => {
+                if self.blank {
+                    if self.blocks[container].children.is_empty() {
+                        // a list item can begin with at most one blank line
+                        return Continuation::NotMatched;
+                    }
+                    self.advance_next_nonspace();
+                } else if self.indent >= data.marker_offset + data.padding {
+                    self.advance_offset(data.marker_offset + data.padding, true);
+                } else {
+                    return Continuation::NotMatched;
+                }
+                Continuation::Matched
+            }
+            // the content of a footnote definition is indented like that of a list item
+            BlockKind::FootnoteDefinition(_) => {
+                if self.indent >= CODE_INDENT {
+                    self.advance_offset(CODE_INDENT, true);
+                } else if self.blank {
+                    self.advance_next_nonspace();
+                } else {
+                    return Continuation::NotMatched;
+                }
+                Continuation::Matched
+            }
+            BlockKind::Heading { .. } | BlockKind::ThematicBreak | BlockKind::Definition => {
+                Continuation::NotMatched
+            }
+            BlockKind::FencedCode {
+                fence_char,
+                fence_length,
+                fence_offset,
+            } => {
+                let rest = self.rest(self.next_nonspace);
+                if self.indent <= 3 && rest.starts_with(fence_char) {
+                    if let Ok((_, fence)) = closing_code_fence(rest) {
+                        if fence.len() >= fence_length {
+                            self.extend_to_line_end(container);
+                            self.finalize(container);
+                            return Continuation::LineDone;
+                        }
+                    }
+                }
+                // skip optional spaces of the fence offset
+                let mut i = fence_offset;
+                while i > 0 && is_space_or_tab(self.peek(self.offset)) {
+                    self.advance_offset(1, true);
+                    i -= 1;
+                }
+                Continuation::Matched
+            }
+            BlockKind::IndentedCode => {
+                if self.indent >= CODE_INDENT {
+                    self.advance_offset(CODE_INDENT, true);
+                } else if self.blank {
+                    self.advance_next_nonspace();
+                } else {
+                    return Continuation::NotMatched;
+                }
+                Continuation::Matched
+            }
+            BlockKind::Html(condition) => {
+                if self.blank && (condition == 6 || condition == 7) {
+                    Continuation::NotMatched
+                } else {
+                    Continuation::Matched
+                }
+            }
+            BlockKind::Paragraph | BlockKind::Table(_) => {
+                if self.blank {
+                    Continuation::NotMatched
+                } else {
+                    Continuation::Matched
+                }
+            }
+        }
+    }
+
+    fn try_block_starts(&mut self, container: usize) -> Start {
+        let rest = self.rest(self.next_nonspace);
+        let is_paragraph = matches!(self.blocks[container].kind, BlockKind::Paragraph);
+
+        if !self.indented {
+            // block quote
+            if self.peek(self.next_nonspace) == Some(b'>') {
+                let start = self.next_nonspace;
+                self.advance_next_nonspace();
+                self.advance_offset(1, false);
+                if is_space_or_tab(self.peek(self.offset)) {
+                    self.advance_offset(1, true);
+                }
+                self.close_unmatched_blocks();
+                self.add_child(BlockKind::BlockQuote, start);
+                return Start::Container;
+            }
+
+            // ATX heading
+            if let Ok((remaining, depth)) = atx_heading_start(rest) {
+                let start = self.next_nonspace;
+                self.advance_next_nonspace();
+                self.advance_offset(rest.len() - remaining.len(), false);
+                self.close_unmatched_blocks();
+                let heading = self.add_child(
+                    BlockKind::Heading {
+                        depth,
+                        setext: false,
+                    },
+                    start,
+                );
+                self.blocks[heading].content =
+                    atx_heading_content(self.rest(self.offset)).to_string();
+                self.blocks[heading].map = SourceMap::new(0, self.line_start + self.offset);
+                self.offset = self.line.len();
+                return Start::Leaf;
+            }
+
+            // fenced code block
+            if let Ok((_, fence)) = code_fence(rest) {
+                self.close_unmatched_blocks();
+                self.add_child(
+                    BlockKind::FencedCode {
+                        fence_char: fence.chars().next().unwrap_or('`'),
+                        fence_length: fence.len(),
+                        fence_offset: self.indent,
+                    },
+                    self.next_nonspace,
+                );
+                self.advance_next_nonspace();
+                self.advance_offset(fence.len(), false);
+                return Start::Leaf;
+            }
+
+            // HTML block
+            if self.peek(self.next_nonspace) == Some(b'<') {
+                let interrupts_paragraph = is_paragraph
+                    || (!self.all_closed
+                        && !self.blank
+                        && matches!(self.blocks[self.tip].kind, BlockKind::Paragraph));
+                if let Some(condition) = html_block_start(rest, interrupts_paragraph) {
+                    self.close_unmatched_blocks();
+                    // spaces are part of the HTML block so the offset is not adjusted
+                    self.add_child(BlockKind::Html(condition), self.next_nonspace);
+                    return Start::Leaf;
+                }
+            }
+
+            // setext heading
+            if is_paragraph {
+                if let Ok((_, depth)) = setext_heading_underline(rest) {
+                    self.close_unmatched_blocks();
+                    self.extract_definitions(container);
+                    if !self.blocks[container].content.is_empty() {
+                        self.blocks[container].kind = BlockKind::Heading {
+                            depth,
+                            setext: true,
+                        };
+                        self.offset = self.line.len();
+                        return Start::Leaf;
+                    }
+                }
+            }
+
+            // thematic break
+            if thematic_break(rest).is_ok() {
+                self.close_unmatched_blocks();
+                self.add_child(BlockKind::ThematicBreak, self.next_nonspace);
+                self.offset = self.line.len();
+                return Start::Leaf;
+            }
+
+            // footnote definition
+            if self.options.footnotes {
+                if let Ok((remaining, label)) = footnote_definition_start(rest) {
+                    let start = self.next_nonspace;
+                    self.advance_next_nonspace();
+                    self.advance_offset(
+                        rest[..rest.len() - remaining.len()].chars().count(),
+                        false,
+                    );
+                    self.close_unmatched_blocks();
+                    self.definitions
+                        .footnotes
+                        .insert(Association::normalize(label));
+                    self.add_child(BlockKind::FootnoteDefinition(label.to_string()), start);
+                    return Start::Container;
+                }
+            }
+        }
+
+        // list item
+        if !self.indented || matches!(self.blocks[container].kind, BlockKind::List(_)) {
+            let start = self.next_nonspace;
+            if let Some(data) = self.parse_list_marker(container) {
+                self.close_unmatched_blocks();
+                let matches_list = match self.blocks[self.tip].kind {
+                    BlockKind::List(list) => list.matches(&data),
+                    _ => false,
+                };
+                if !matches_list {
+                    self.add_child(BlockKind::List(data), start);
+                }
+                self.add_child(BlockKind::Item(data), start);
+                return Start::Container;
+            }
+        }
+
+        // table
+        if !self.indented && is_paragraph && self.options.tables {
+            if let Some(align) = table_delimiter_row(rest) {
+                if self.start_table(container, align) {
+                    self.advance_next_nonspace();
+                    return Start::Leaf;
+                }
+            }
+        }
+
+        // indented code block
+        if self.indented
+            && !matches!(
+                self.blocks[self.tip].kind,
+                BlockKind::Paragraph | BlockKind::Table(_)
+            )
+            && !self.blank
+        {
+            let start = self.offset;
+            self.advance_offset(CODE_INDENT, true);
+            self.close_unmatched_blocks();
+            self.add_child(BlockKind::IndentedCode, start);
+            return Start::Leaf;
+        }
+
+        Start::None
+    }
+
+    fn parse_list_marker(&mut self, container: usize) -> Option<ListData> {
+        if self.indent >= CODE_INDENT {
+            return None;
+        }
+
+        let rest = self.rest(self.next_nonspace);
+        let is_paragraph = matches!(self.blocks[container].kind, BlockKind::Paragraph);
+        let (remaining, (ordered, marker, start)) = list_marker(rest).ok()?;
+
+        // In order for a sequence of lines to constitute a list item, an ordered list
+        // interrupting a paragraph must start with 1.
+        if ordered && is_paragraph && start != 1 {
+            return None;
+        }
+
+        // the marker must be followed by spaces or tabs or the end of the line
+        let marker_length = rest.len() - remaining.len();
+        if !(remaining.is_empty() || remaining.starts_with(' ') || remaining.starts_with('\t')) {
+            return None;
+        }
+
+        // a list item can interrupt a paragraph only if it does not start with a blank line
+        if is_paragraph && is_blank(remaining) {
+            return None;
+        }
+
+        let marker_offset = self.indent;
+        self.advance_next_nonspace();
+        self.advance_offset(marker_length, true);
+        let spaces_start_column = self.column;
+        let spaces_start_offset = self.offset;
+        loop {
+            self.advance_offset(1, true);
+            if !(self.column - spaces_start_column < 5 && is_space_or_tab(self.peek(self.offset))) {
+                break;
+            }
+        }
+        let blank_item = self.peek(self.offset).is_none();
+        let spaces_after_marker = self.column - spaces_start_column;
+        let padding = if !(1..5).contains(&spaces_after_marker) || blank_item {
+            // the content starts one space after the marker
+            self.column = spaces_start_column;
+            self.offset = spaces_start_offset;
+            if is_space_or_tab(self.peek(self.offset)) {
+                self.advance_offset(1, true);
+            }
+            marker_length + 1
+        } else {
+            marker_length + spaces_after_marker
+        };
+
+        Some(ListData {
+            ordered,
+            marker,
+            start,
+            padding,
+            marker_offset,
+        })
+    }
+
+    // The last line of a paragraph becomes the header row of a table when it has as many cells as the
+    // delimiter row. The lines before it remain a paragraph.
+    fn start_table(&mut self, paragraph: usize, align: Vec<AlignType>) -> bool {
+        let block = &self.blocks[paragraph];
+        let content = block.content.trim_end_matches('\n');
+        let header_start = content.rfind('\n').map_or(0, |i| i + 1);
+        let header = &content[header_start..];
+        if table_cells(header).len() != align.len() {
+            return false;
+        }
+        let header = format!("{}\n", header);
+        let map = block.map.skip(header_start);
+        let indent = header.len() - header.trim_start_matches([' ', '\t']).len();
+        let start = map.start(indent);
+
+        self.close_unmatched_blocks();
+        let block = &mut self.blocks[paragraph];
+        block.content.truncate(header_start);
+        let before = block.content.trim_end().len();
+        block.end = block.map.end(before);
+        self.finalize(paragraph);
+
+        let table = self.add_child(BlockKind::Table(align), self.next_nonspace);
+        let block = &mut self.blocks[table];
+        block.start = start;
+        block.content = header;
+        block.map = map;
+        true
+    }
+
+    // Link reference definitions at the start of a paragraph are turned into definition blocks
+    // inserted before the paragraph. Each block keeps the source of its definition as content.
+    fn extract_definitions(&mut self, paragraph: usize) {
+        let mut definitions = Vec::new();
+        let mut consumed = 0;
+        {
+            let content = &self.blocks[paragraph].content;
+            while content[consumed..].starts_with('[') {
+                match link_reference_definition(&content[consumed..]) {
+                    Ok((remaining, definition)) => {
+                        let source = &content[consumed..content.len() - remaining.len()];
+                        definitions.push((consumed, source.to_string()));
+                        consumed += source.len();
+                        self.definitions
+                            .links
+                            .insert(Association::normalize(definition.label));
+                    }
+                    Err(_) => break,
+                }
+            }
+        }
+        if definitions.is_empty() {
+            return;
+        }
+
+        let map = self.blocks[paragraph].map.clone();
+        let block = &mut self.blocks[paragraph];
+        block.content.drain(..consumed);
+        block.map = map.skip(consumed);
+        let indent = block.content.len() - block.content.trim_start().len();
+        block.start = block.map.start(indent);
+
+        let parent = block.parent.unwrap_or(DOCUMENT);
+        let position = self.blocks[parent]
+            .children
+            .iter()
+            .position(|&child| child == paragraph)
+            .unwrap_or(0);
+        for (i, (offset, content)) in definitions.into_iter().enumerate() {
+            let id = self.blocks.len();
+            let end = offset + content.trim_end().len();
+            self.blocks.push(Block {
+                kind: BlockKind::Definition,
+                parent: Some(parent),
+                children: Vec::new(),
+                open: false,
+                content,
+                map: map.skip(offset),
+                start: map.start(offset),
+                end: map.end(end),
+            });
+            self.blocks[parent].children.insert(position + i, id);
+        }
+    }
+
+    fn finalize(&mut self, block: usize) {
+        self.blocks[block].open = false;
+        match self.blocks[block].kind {
+            BlockKind::Paragraph => {
+                self.extract_definitions(block);
+                if is_blank(&self.blocks[block].content) {
+                    self.unlink(block);
+                }
+            }
+            BlockKind::IndentedCode => {
+                // trailing blank lines are not part of an indented code block
+                let lines: Vec<&str> = self.blocks[block].content.split('\n').collect();
+                let keep = lines
+                    .iter()
+                    .rposition(|line| !is_blank(line))
+                    .map_or(0, |last| last + 1);
+                let content = lines[..keep].join("\n");
+                self.blocks[block].content = content;
+            }
+            _ => {}
+        }
+        self.tip = self.blocks[block].parent.unwrap_or(DOCUMENT);
+    }
+
+    // Whether a blank line separates any two children of the block, that is whether one of them ends more
+    // than a line before the next one starts. This is how mdast defines the spread of lists and list items,
+    // and a list is loose when either it or one of its items is spread.
+    // https://spec.commonmark.org/0.30/#loose
+    fn spread(&self, id: usize) -> bool {
+        self.blocks[id].children.windows(2).any(|pair| {
+            let end = self.source.point(self.blocks[pair[0]].end).line;
+            let start = self.source.point(self.blocks[pair[1]].start).line;
+            start > end + 1
+        })
+    }
+
+    fn position(&self, block: &Block) -> Option<Position> {
+        Some(self.source.position(block.start, block.end))
+    }
+
+    fn phrasing(&self, content: &str, map: &SourceMap) -> Vec<PhrasingContent> {
+        let (content, map) = paragraph_content(content, map);
+        let locator = Locator {
+            lines: self.source,
+            map: &map,
+        };
+        parse_inlines(&content, self.options, &self.definitions, &locator)
+    }
+
+    // Cells are parsed as phrasing content after `\|` is replaced by a pipe, even in code spans.
+    // Any pipe left in a cell is escaped, otherwise it would have ended the cell.
+    // Rows get as many cells as there are columns, either by dropping the ones that do not fit
+    // or by adding empty cells.
+    fn table_row(&self, line: &str, source: usize, columns: usize) -> TableRow {
+        let cells = table_cells(line);
+        let last = cells.len() - 1;
+        let mut children = Vec::with_capacity(columns);
+        for (i, &(start, end)) in cells.iter().enumerate().take(columns) {
+            let cell = &line[start..end];
+            let trimmed = cell.trim_start_matches([' ', '\t']);
+            let offset = start + cell.len() - trimmed.len();
+            let trimmed = trimmed.trim_end_matches([' ', '\t']);
+
+            let mut text = String::with_capacity(trimmed.len());
+            let mut map = SourceMap::new(0, source + offset);
+            let mut rest = trimmed;
+            while let Some(pipe) = rest.find('|') {
+                // leave out the backslash before the pipe
+                text.push_str(&rest[..pipe - 1]);
+                let skipped = offset + (trimmed.len() - rest.len()) + pipe;
+                map.push(text.len(), source + skipped);
+                text.push('|');
+                rest = &rest[pipe + 1..];
+            }
+            text.push_str(rest);
+            let locator = Locator {
+                lines: self.source,
+                map: &map,
+            };
+
+            // a cell spans from the pipe before it to the next one, or to the end of the row
+            let start = match start.checked_sub(1) {
+                Some(pipe) if line.as_bytes()[pipe] == b'|' => pipe,
+                _ => start,
+            };
+            let end = if i == last && line.as_bytes().get(end) == Some(&b'|') {
+                end + 1
+            } else {
+                end
+            };
+            children.push(RowContent::TableCell(TableCell {
+                children: parse_inlines(&text, self.options, &self.definitions, &locator),
+                position: Some(self.source.position(source + start, source + end)),
+            }));
+        }
+        while children.len() < columns {
+            children.push(RowContent::TableCell(TableCell {
+                children: Vec::new(),
+                position: None,
+            }));
+        }
+        let start = line.len() - line.trim_start_matches([' ', '\t']).len();
+        let end = line.trim_end_matches([' ', '\t']).len();
+        TableRow {
+            children,
+            position: Some(self.source.position(source + start, source + end)),
+        }
+    }
+
+    // With task lists enabled, a task list marker at the start of the first paragraph of an item
+    // is taken out of the paragraph and sets whether the item is checked.
+    fn list_item(&self, id: usize) -> ListItem {
+        let block = &self.blocks[id];
+        let mut children = self.flow_children(id);
+        let mut checked = None;
+        let paragraph = block
+            .children
+            .first()
+            .map(|&child| &self.blocks[child])
+            .filter(|child| matches!(child.kind, BlockKind::Paragraph));
+        if let Some(paragraph) = paragraph.filter(|_| self.options.task_lists) {
+            if let Ok((rest, done)) = task_list_marker(&paragraph.content) {
+                let map = paragraph.map.skip(paragraph.content.len() - rest.len());
+                let start = map.start(0);
+                children[0] = FlowContent::Content(Content::Paragraph(Paragraph {
+                    children: self.phrasing(rest, &map),
+                    position: Some(self.source.position(start, paragraph.end)),
+                }));
+                checked = Some(done);
+            }
+        }
+        ListItem {
+            spread: Some(self.spread(id)),
+            checked,
+            children,
+            position: self.position(block),
+        }
+    }
+
+    fn flow_content(&self, id: usize) -> FlowContent {
+        let block = &self.blocks[id];
+        let position = self.position(block);
+        match block.kind {
+            BlockKind::Document | BlockKind::Item(_) => {
+                unreachable!("documents and list items are converted by their parent")
+            }
+            BlockKind::BlockQuote => FlowContent::BlockQuote(BlockQuote {
+                children: self.flow_children(id),
+                position,
+            }),
+            BlockKind::List(data) => FlowContent::List(List {
+                ordered: Some(data.ordered),
+                start: if data.ordered { Some(data.start) } else { None },
+                spread: Some(self.spread(id)),
+                children: block
+                    .children
+                    .iter()
+                    .map(|&item| ListContent::ListItem(self.list_item(item)))
+                    .collect(),
+                position,
+            }),
+            BlockKind::Paragraph => FlowContent::Content(Content::Paragraph(Paragraph {
+                children: self.phrasing(&block.content, &block.map),
+                position,
+            })),
+            BlockKind::Heading { depth, setext } => FlowContent::Heading(Heading {
+                depth,
+                setext,
+                children: self.phrasing(&block.content, &block.map),
+                position,
+            }),
+            BlockKind::ThematicBreak => FlowContent::ThematicBreak(ThematicBreak { position }),
+            BlockKind::IndentedCode => FlowContent::Code(Code {
+                lang: None,
+                meta: None,
+                raw_lang: None,
+                raw_meta: None,
+                meta_start: None,
+                value: block.content.as_bytes().to_vec(),
+                position,
+            }),
+            BlockKind::FencedCode { .. } => {
+                // the first line is the info string
+                let (info, value) = block
+                    .content
+                    .split_at(block.content.find('\n').unwrap_or(0));
+                let value = value.strip_prefix('\n').unwrap_or(value);
+                let value = value.strip_suffix('\n').unwrap_or(value);
+                let (lang, meta) = info_string(info);
+                // the meta is trimmed so it ends where the trimmed info string does
+                let meta_start = meta.map(|meta| {
+                    let offset = info.trim_end_matches([' ', '\t']).len() - meta.len();
+                    self.source.point(block.map.start(offset))
+                });
+                let (lang, raw_lang) = unescape_raw_option(lang);
+                let (meta, raw_meta) = unescape_raw_option(meta);
+                FlowContent::Code(Code {
+                    lang,
+                    meta,
+                    raw_lang,
+                    raw_meta,
+                    meta_start,
+                    value: value.as_bytes().to_vec(),
+                    position,
+                })
+            }
+            BlockKind::Html(_) => FlowContent::HTML(HTML {
+                value: block.content.trim_end_matches('\n').as_bytes().to_vec(),
+                position,
+            }),
+            BlockKind::Definition => {
+                let (_, definition) = link_reference_definition(&block.content)
+                    .expect("definition blocks hold a valid definition");
+                let (url, raw_url) = unescape_raw(definition.destination);
+                let (title, raw_title) = unescape_raw_option(definition.title);
+                FlowContent::Content(Content::Definition(Definition {
+                    url,
+                    title,
+                    raw_url,
+                    raw_title,
+                    association: Association::new(definition.label),
+                    position,
+                }))
+            }
+            BlockKind::FootnoteDefinition(ref label) => {
+                FlowContent::FootnoteDefinition(FootnoteDefinition {
+                    association: Association::new(label),
+                    children: self.flow_children(id),
+                    position,
+                })
+            }
+            BlockKind::Table(ref align) => {
+                let mut children = Vec::new();
+                let mut offset = 0;
+                for (i, line) in block.content.trim_end_matches('\n').split('\n').enumerate() {
+                    // the second line is the delimiter row
+                    if i != 1 {
+                        let row = self.table_row(line, block.map.start(offset), align.len());
+                        children.push(TableContent::TableRow(row));
+                    }
+                    offset += line.len() + 1;
+                }
+                FlowContent::Table(Table {
+                    align: Some(align.clone()),
+                    children,
+                    position,
+                })
+            }
+        }
+    }
+
+    fn flow_children(&self, id: usize) -> Vec<FlowContent> {
+        self.blocks[id]
+            .children
+            .iter()
+            .map(|&child| self.flow_content(child))
+            .collect()
+    }
+}
+
+// The optional closing sequence of #s must be preceded by spaces or tabs and may be followed by spaces or tabs only.
+fn atx_heading_content(rest: &str) -> &str {
+    let trimmed = rest.trim_end_matches([' ', '\t']);
+    let without_closing = trimmed.trim_end_matches('#');
+    if without_closing.len() == trimmed.len() {
+        return trimmed;
+    }
+    if without_closing.is_empty() {
+        return "";
+    }
+    if without_closing.ends_with(' ') || without_closing.ends_with('\t') {
+        return without_closing.trim_end_matches([' ', '\t']);
+    }
+    trimmed
+}
+
+// Leading spaces or tabs are stripped from each line of a paragraph, trailing ones only from the last line
+// since the others may end in a hard line break.
+fn paragraph_content(content: &str, map: &SourceMap) -> (String, SourceMap) {
+    let mut text = String::new();
+    let mut text_map = SourceMap::default();
+    let mut offset = 0;
+    for (i, line) in content.trim_end_matches('\n').split('\n').enumerate() {
+        if i > 0 {
+            text.push('\n');
+        }
+        let trimmed = line.trim_start_matches([' ', '\t']);
+        text_map.push(text.len(), map.start(offset + line.len() - trimmed.len()));
+        text.push_str(trimmed);
+        offset += line.len() + 1;
+    }
+    let end = text.trim_end_matches([' ', '\t']).len();
+    text.truncate(end);
+    (text, text_map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_endings() {
+        assert_eq!(
+            lines("a\nb\r\nc\rd\n"),
+            vec![(0, "a"), (2, "b"), (5, "c"), (7, "d")]
+        );
+        assert_eq!(lines("a\n\n"), vec![(0, "a"), (2, "")]);
+    }
+
+    #[test]
+    fn recognizers() {
+        assert_eq!(atx_heading_start("### foo"), Ok(("foo", 3)));
+        assert_eq!(atx_heading_start("#"), Ok(("", 1)));
+        assert!(atx_heading_start("#5 bolt").is_err());
+        assert!(atx_heading_start("####### foo").is_err());
+        assert!(thematic_break("- - -").is_ok());
+        assert!(thematic_break("**").is_err());
+        assert!(thematic_break("_ _ _ a").is_err());
+        assert_eq!(setext_heading_underline("===  "), Ok(("", 1)));
+        assert!(setext_heading_underline("= =").is_err());
+        assert!(code_fence("``` rust").is_ok());
+        assert!(code_fence("``` a`b").is_err());
+        assert!(code_fence("~~~ a`b").is_ok());
+        assert_eq!(info_string(" rust "), (Some("rust"), None));
+        assert_eq!(
+            info_string("js \t title=\"a b\"  {1} "),
+            (Some("js"), Some("title=\"a b\"  {1}"))
+        );
+        assert_eq!(info_string(" \t"), (None, None));
+        assert_eq!(list_marker("10) a"), Ok((" a", (true, ')', 10))));
+        assert_eq!(list_marker("- a"), Ok((" a", (false, '-', 1))));
+        assert!(list_marker("1234567890. a").is_err());
+        assert_eq!(task_list_marker("[x] done"), Ok(("done", true)));
+        assert_eq!(task_list_marker("[ ]\ttodo"), Ok(("todo", false)));
+        assert!(task_list_marker("[x]done").is_err());
+        assert!(task_list_marker("[x] \ntext").is_err());
+        assert!(task_list_marker("[-] todo").is_err());
+    }
+
+    #[test]
+    fn table_rows() {
+        assert_eq!(table_cells("| a | b |"), vec![(1, 4), (5, 8)]);
+        assert_eq!(table_cells("a|b"), vec![(0, 1), (2, 3)]);
+        assert_eq!(table_cells("  | a \\| b  "), vec![(3, 10)]);
+        assert_eq!(table_cells("a \\\\| b"), vec![(0, 4), (5, 7)]);
+        assert_eq!(table_cells("a \\\\\\| b"), vec![(0, 8)]);
+        assert_eq!(table_cells("|"), vec![(1, 1)]);
+        assert_eq!(
+            table_delimiter_row("|:--| --: |:-:| - |"),
+            Some(vec![
+                AlignType::Left,
+                AlignType::Right,
+                AlignType::Center,
+                AlignType::None
+            ])
+        );
+        assert_eq!(table_delimiter_row("---"), None);
+        assert_eq!(table_delimiter_row("| -- | |"), None);
+        assert_eq!(table_delimiter_row("| ::- |"), None);
+        assert_eq!(table_delimiter_row("| - - |"), None);
+    }
+
+    #[test]
+    fn atx_closing_sequence() {
+        assert_eq!(atx_heading_content("foo ##  "), "foo");
+        assert_eq!(atx_heading_content("foo#"), "foo#");
+        assert_eq!(atx_heading_content("###"), "");
+        assert_eq!(atx_heading_content("foo \\###"), "foo \\###");
+    }
+}
@@ -0,0 +1,177 @@
+use super::entities::ENTITIES;
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take_while1, take_while_m_n},
+    character::complete::{char, one_of},
+    combinator::map_opt,
+    sequence::{delimited, preceded},
+    IResult,
+};
+
+// Entity and numeric character references are recognized in any context besides code spans, code blocks
+// and raw HTML, including URLs, link titles, and fenced code block info strings.
+// https://spec.commonmark.org/0.30/#entity-and-numeric-character-references
+
+// Code points that cannot be represented, including U+0000, are replaced by the replacement character.
+fn code_point(value: u32) -> char {
+    match value {
+        0 => '\u{fffd}',
+        _ => char::from_u32(value).unwrap_or('\u{fffd}'),
+    }
+}
+
+// An entity reference consists of & + any of the valid HTML5 entity names + ;.
+fn entity_reference(i: &str) -> IResult<&str, String> {
+    map_opt(
+        delimited(
+            char('&'),
+            take_while1(|c: char| c.is_ascii_alphanumeric()),
+            char(';'),
+        ),
+        |name: &str| {
+            ENTITIES
+                .binary_search_by_key(&name, |&(name, _)| name)
+                .ok()
+                .map(|index| ENTITIES[index].1.to_string())
+        },
+    )(i)
+}
+
+// A decimal numeric character reference consists of &# + a string of 1–7 arabic digits + ;.
+// A hexadecimal numeric character reference consists of &# + either X or x + a string of 1-6 hexadecimal
+// digits + ;.
+fn numeric_reference(i: &str) -> IResult<&str, String> {
+    let decimal = map_opt(
+        take_while_m_n(1, 7, |c: char| c.is_ascii_digit()),
+        |d: &str| d.parse().ok(),
+    );
+    let hexadecimal = map_opt(
+        preceded(
+            one_of("xX"),
+            take_while_m_n(1, 6, |c: char| c.is_ascii_hexdigit()),
+        ),
+        |h| u32::from_str_radix(h, 16).ok(),
+    );
+    let (rest, value) = delimited(tag("&#"), alt((hexadecimal, decimal)), char(';'))(i)?;
+    Ok((rest, code_point(value).to_string()))
+}
+
+/// Parses an entity or numeric character reference, returning the text it stands for.
+pub(crate) fn character_reference(i: &str) -> IResult<&str, String> {
+    alt((entity_reference, numeric_reference))(i)
+}
+
+// Replaces character references by the text they stand for, and backslash escapes by the escaped
+// character when `escapes` is set. Also returns the offset in `i` that each byte of the result comes
+// from, followed by the length of `i`.
+fn decode(i: &str, escapes: bool) -> (String, Vec<usize>) {
+    let mut out = String::with_capacity(i.len());
+    let mut offsets = Vec::with_capacity(i.len() + 1);
+    let mut push = |out: &mut String, text: &str, offset: usize, whole: bool| {
+        out.push_str(text);
+        offsets.extend((0..text.len()).map(|k| if whole { offset } else { offset + k }));
+    };
+    let mut rest = i;
+    while let Some(found) = rest.find(|c| c == '&' || escapes && c == '\\') {
+        push(&mut out, &rest[..found], i.len() - rest.len(), false);
+        rest = &rest[found..];
+        let offset = i.len() - rest.len();
+        if let Some(escaped) = rest
+            .strip_prefix('\\')
+            .and_then(|after| after.chars().next())
+            .filter(char::is_ascii_punctuation)
+        {
+            push(&mut out, escaped.encode_utf8(&mut [0; 4]), offset, true);
+            rest = &rest[2..];
+        } else if let Ok((after, decoded)) = character_reference(rest) {
+            push(&mut out, &decoded, offset, true);
+            rest = after;
+        } else {
+            push(&mut out, &rest[..1], offset, false);
+            rest = &rest[1..];
+        }
+    }
+    push(&mut out, rest, i.len() - rest.len(), false);
+    offsets.push(i.len());
+    (out, offsets)
+}
+
+/// Resolves the backslash escapes and character references of a link destination, link title or
+/// info string.
+pub(crate) fn unescape(i: &str) -> String {
+    decode(i, true).0
+}
+
+/// Resolves like [`unescape`], also returning the offset in `i` of each byte of the value and of its end.
+pub(crate) fn unescape_with_offsets(i: &str) -> (String, Vec<usize>) {
+    decode(i, true)
+}
+
+/// The source of a value when resolving its escapes and references changed it, to keep its spelling.
+pub(crate) fn raw(source: &str, value: &str) -> Option<String> {
+    Some(source)
+        .filter(|source| *source != value)
+        .map(str::to_string)
+}
+
+/// Resolves like [`unescape`], also returning the source when it differs from the value.
+pub(crate) fn unescape_raw(i: &str) -> (String, Option<String>) {
+    let value = unescape(i);
+    let raw = raw(i, &value);
+    (value, raw)
+}
+
+/// Resolves an optional value like [`unescape_raw`].
+pub(crate) fn unescape_raw_option(i: Option<&str>) -> (Option<String>, Option<String>) {
+    i.map(unescape_raw)
+        .map_or((None, None), |(value, raw)| (Some(value), raw))
+}
+
+/// Resolves the character references of an autolink, where backslashes are literal.
+pub(crate) fn decode_references(i: &str) -> String {
+    decode(i, false).0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn references() {
+        assert_eq!(character_reference("&amp; b"), Ok((" b", "&".to_string())));
+        assert_eq!(
+            character_reference("&ngE;"),
+            Ok(("", "\u{2267}\u{338}".to_string()))
+        );
+        assert_eq!(character_reference("&#35;"), Ok(("", "#".to_string())));
+        assert_eq!(character_reference("&#X22;"), Ok(("", "\"".to_string())));
+        assert_eq!(
+            character_reference("&#0;"),
+            Ok(("", "\u{fffd}".to_string()))
+        );
+        assert_eq!(
+            character_reference("&#x110000;"),
+            Ok(("", "\u{fffd}".to_string()))
+        );
+        assert!(character_reference("&nbsp").is_err());
+        assert!(character_reference("&MadeUpEntity;").is_err());
+        assert!(character_reference("&#87654321;").is_err());
+        assert!(character_reference("&#xabcdef0;").is_err());
+        assert!(character_reference("&#;").is_err());
+    }
+
+    #[test]
+    fn unescaping() {
+        assert_eq!(unescape("/f&ouml;\\*o\\a&x"), "/fö*o\\a&x");
+        assert_eq!(decode_references("http://a/\\[&amp;"), "http://a/\\[&");
+        assert_eq!(
+            unescape_with_offsets("a\\\"&amp;b"),
+            ("a\"&b".to_string(), vec![0, 1, 3, 8, 9])
+        );
+    }
+
+    #[test]
+    fn sorted() {
+        assert!(ENTITIES.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+}
@@ -0,0 +1,309 @@
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take_until, take_while, take_while1},
+    character::complete::{char, line_ending, satisfy, space0},
+    combinator::{eof, opt, peek, recognize, verify},
+    multi::many0,
+    sequence::{delimited, pair, preceded, tuple},
+    IResult,
+};
+
+// https://spec.commonmark.org/0.30/#html-blocks
+// start condition 1
+const LITERAL_TAG_NAMES: [&str; 4] = ["pre", "script", "style", "textarea"];
+
+// start condition 6
+const BLOCK_TAG_NAMES: [&str; 62] = [
+    "address",
+    "article",
+    "aside",
+    "base",
+    "basefont",
+    "blockquote",
+    "body",
+    "caption",
+    "center",
+    "col",
+    "colgroup",
+    "dd",
+    "details",
+    "dialog",
+    "dir",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "frame",
+    "frameset",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "head",
+    "header",
+    "hr",
+    "html",
+    "iframe",
+    "legend",
+    "li",
+    "link",
+    "main",
+    "menu",
+    "menuitem",
+    "nav",
+    "noframes",
+    "ol",
+    "optgroup",
+    "option",
+    "p",
+    "param",
+    "section",
+    "source",
+    "summary",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "title",
+    "tr",
+    "track",
+    "ul",
+];
+
+// spaces, tabs, and up to one line ending
+fn whitespace0(i: &str) -> IResult<&str, &str> {
+    recognize(tuple((space0, opt(line_ending), space0)))(i)
+}
+
+fn whitespace1(i: &str) -> IResult<&str, &str> {
+    verify(whitespace0, |s: &str| !s.is_empty())(i)
+}
+
+// A tag name consists of an ASCII letter followed by zero or more ASCII letters, digits, or hyphens (-).
+fn tag_name(i: &str) -> IResult<&str, &str> {
+    recognize(pair(
+        satisfy(|c| c.is_ascii_alphabetic()),
+        take_while(|c: char| c.is_ascii_alphanumeric() || c == '-'),
+    ))(i)
+}
+
+// An attribute name consists of an ASCII letter, _, or :, followed by zero or more ASCII letters, digits, _, ., :, or -.
+fn attribute_name(i: &str) -> IResult<&str, &str> {
+    recognize(pair(
+        satisfy(|c| c.is_ascii_alphabetic() || c == '_' || c == ':'),
+        take_while(|c: char| c.is_ascii_alphanumeric() || "_.:-".contains(c)),
+    ))(i)
+}
+
+fn attribute_value(i: &str) -> IResult<&str, &str> {
+    alt((
+        take_while1(|c: char| !" \t\r\n\"'=<>`".contains(c)),
+        recognize(delimited(char('\''), take_while(|c| c != '\''), char('\''))),
+        recognize(delimited(char('"'), take_while(|c| c != '"'), char('"'))),
+    ))(i)
+}
+
+fn attribute(i: &str) -> IResult<&str, &str> {
+    recognize(tuple((
+        whitespace1,
+        attribute_name,
+        opt(tuple((
+            whitespace0,
+            char('='),
+            whitespace0,
+            attribute_value,
+        ))),
+    )))(i)
+}
+
+pub(crate) fn open_tag(i: &str) -> IResult<&str, &str> {
+    recognize(tuple((
+        char('<'),
+        tag_name,
+        many0(attribute),
+        whitespace0,
+        opt(char('/')),
+        char('>'),
+    )))(i)
+}
+
+pub(crate) fn closing_tag(i: &str) -> IResult<&str, &str> {
+    recognize(tuple((tag("</"), tag_name, whitespace0, char('>'))))(i)
+}
+
+// An HTML comment consists of <!-- + text + -->, where text does not start with > or ->, does not end with -,
+// and does not contain --.
+fn comment(i: &str) -> IResult<&str, &str> {
+    recognize(delimited(
+        tag("<!--"),
+        verify(take_until("--"), |text: &str| {
+            !text.starts_with('>') && !text.starts_with("->") && !text.ends_with('-')
+        }),
+        tag("-->"),
+    ))(i)
+}
+
+// A processing instruction consists of the string <?, a string of characters not including the string ?>,
+// and the string ?>.
+fn processing_instruction(i: &str) -> IResult<&str, &str> {
+    recognize(tuple((tag("<?"), take_until("?>"), tag("?>"))))(i)
+}
+
+// A declaration consists of the string <!, an ASCII letter, zero or more characters not including the
+// character >, and the character >.
+fn declaration(i: &str) -> IResult<&str, &str> {
+    recognize(tuple((
+        tag("<!"),
+        satisfy(|c| c.is_ascii_alphabetic()),
+        take_while(|c| c != '>'),
+        char('>'),
+    )))(i)
+}
+
+// A CDATA section consists of the string <![CDATA[, a string of characters not including the string ]]>,
+// and the string ]]>.
+fn cdata_section(i: &str) -> IResult<&str, &str> {
+    recognize(tuple((tag("<![CDATA["), take_until("]]>"), tag("]]>"))))(i)
+}
+
+// An HTML tag consists of an open tag, a closing tag, an HTML comment, a processing instruction,
+// a declaration, or a CDATA section.
+// https://spec.commonmark.org/0.30/#raw-html
+pub(crate) fn html_tag(i: &str) -> IResult<&str, &str> {
+    alt((
+        open_tag,
+        closing_tag,
+        comment,
+        processing_instruction,
+        cdata_section,
+        declaration,
+    ))(i)
+}
+
+fn end_of_tag_name(i: &str) -> IResult<&str, &str> {
+    peek(alt((tag(" "), tag("\t"), tag(">"), tag("/>"), eof)))(i)
+}
+
+fn literal_start(i: &str) -> IResult<&str, &str> {
+    let (rest, name) = preceded(char('<'), tag_name)(i)?;
+    if LITERAL_TAG_NAMES
+        .iter()
+        .any(|n| n.eq_ignore_ascii_case(name))
+    {
+        end_of_tag_name(rest)
+    } else {
+        Err(nom::Err::Error(nom::error::Error::new(
+            i,
+            nom::error::ErrorKind::Tag,
+        )))
+    }
+}
+
+fn block_tag_start(i: &str) -> IResult<&str, &str> {
+    let (rest, name) = preceded(pair(char('<'), opt(char('/'))), tag_name)(i)?;
+    if BLOCK_TAG_NAMES.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+        end_of_tag_name(rest)
+    } else {
+        Err(nom::Err::Error(nom::error::Error::new(
+            i,
+            nom::error::ErrorKind::Tag,
+        )))
+    }
+}
+
+fn complete_tag_line(i: &str) -> IResult<&str, &str> {
+    let (rest, t) = alt((open_tag, closing_tag))(i)?;
+    let (_, name) = preceded(pair(char('<'), opt(char('/'))), tag_name)(t)?;
+    if LITERAL_TAG_NAMES
+        .iter()
+        .any(|n| n.eq_ignore_ascii_case(name))
+    {
+        return Err(nom::Err::Error(nom::error::Error::new(
+            i,
+            nom::error::ErrorKind::Tag,
+        )));
+    }
+    recognize(pair(space0, eof))(rest)
+}
+
+/// Returns which of the seven HTML block start conditions the line, with indentation already
+/// removed, satisfies. Condition 7 cannot interrupt a paragraph.
+pub(crate) fn html_block_start(line: &str, interrupts_paragraph: bool) -> Option<u8> {
+    if literal_start(line).is_ok() {
+        Some(1)
+    } else if line.starts_with("<!--") {
+        Some(2)
+    } else if line.starts_with("<?") {
+        Some(3)
+    } else if line.starts_with("<!") && line[2..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+        Some(4)
+    } else if line.starts_with("<![CDATA[") {
+        Some(5)
+    } else if block_tag_start(line).is_ok() {
+        Some(6)
+    } else if !interrupts_paragraph && complete_tag_line(line).is_ok() {
+        Some(7)
+    } else {
+        None
+    }
+}
+
+/// Whether the line satisfies the end condition of an HTML block started by `condition`.
+/// Conditions 6 and 7 end at a blank line which is handled by the block parser itself.
+pub(crate) fn html_block_end(condition: u8, line: &str) -> bool {
+    match condition {
+        1 => {
+            let lower = line.to_ascii_lowercase();
+            LITERAL_TAG_NAMES
+                .iter()
+                .any(|name| lower.contains(&format!("</{}>", name)))
+        }
+        2 => line.contains("-->"),
+        3 => line.contains("?>"),
+        4 => line.contains('>'),
+        5 => line.contains("]]>"),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags() {
+        assert!(open_tag("<a href=\"x\" data-y='z' disabled>").is_ok());
+        assert!(open_tag("<br/>").is_ok());
+        assert!(open_tag("<a h*#ref=\"hi\">").is_err());
+        assert!(closing_tag("</div >").is_ok());
+        assert!(closing_tag("</div foo>").is_err());
+        assert_eq!(html_tag("<!-- a\n- b --> c"), Ok((" c", "<!-- a\n- b -->")));
+        assert!(html_tag("<!--> -->").is_err());
+        assert!(html_tag("<!-- a -- b -->").is_err());
+        assert_eq!(html_tag("<?php echo 1; ?>"), Ok(("", "<?php echo 1; ?>")));
+        assert_eq!(html_tag("<!DOCTYPE html>x"), Ok(("x", "<!DOCTYPE html>")));
+        assert_eq!(html_tag("<![CDATA[a]]b]]>"), Ok(("", "<![CDATA[a]]b]]>")));
+    }
+
+    #[test]
+    fn block_start_conditions() {
+        assert_eq!(html_block_start("<pre>", false), Some(1));
+        assert_eq!(html_block_start("<!-- comment", false), Some(2));
+        assert_eq!(html_block_start("<?php", false), Some(3));
+        assert_eq!(html_block_start("<!DOCTYPE html>", false), Some(4));
+        assert_eq!(html_block_start("<![CDATA[", false), Some(5));
+        assert_eq!(html_block_start("</DIV>", false), Some(6));
+        assert_eq!(html_block_start("<details open>", false), Some(6));
+        assert_eq!(html_block_start("<custom-tag>  ", false), Some(7));
+        assert_eq!(html_block_start("<custom-tag>  ", true), None);
+        assert_eq!(html_block_start("<a> text", false), None);
+    }
+}
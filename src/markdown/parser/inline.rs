@@ -0,0 +1,1490 @@
+use super::autolink::{
+    email_autolink, email_autolink_literal, is_email_local, uri_autolink, url_autolink,
+    www_autolink,
+};
+use super::entity::{
+    character_reference, decode_references, raw, unescape_raw, unescape_raw_option,
+};
+use super::html::html_tag;
+use super::link::{footnote_label, inline_link, link_label};
+use super::position::Locator;
+use crate::ast::{
+    Alternative, Association, Break, Delete, Emphasis, Footnote, FootnoteReference, Image,
+    ImageReference, InlineCode, Link, LinkReference, PhrasingContent, Position, Reference,
+    ReferenceType, StaticPhrasingContent, Strong, Text, HTML,
+};
+use crate::markdown::Options;
+use nom::{bytes::complete::take_while1, IResult};
+use std::collections::HashSet;
+
+// Inlines are parsed with the approach of the CommonMark reference implementation: a single pass over the
+// content that produces text, finished nodes, brackets and delimiter runs. Brackets are resolved into links
+// and images when their closing bracket is found while delimiter runs are matched up into emphasis and
+// strong nodes once the extent of their parent is known. GFM strikethrough is handled as one more kind of
+// delimiter run, made of tildes.
+// https://spec.commonmark.org/0.30/#phrasing-content-algorithm
+
+// Unicode punctuation as used by the flanking rules: ASCII punctuation plus the general punctuation
+// blocks. The spec asks for the whole Pc, Pd, Pe, Pf, Pi, Po and Ps categories which we approximate
+// with the blocks that contain nearly all of them.
+fn is_punctuation(c: char) -> bool {
+    c.is_ascii_punctuation()
+        || matches!(c,
+            '\u{a1}' | '\u{a7}' | '\u{ab}' | '\u{b6}' | '\u{b7}' | '\u{bb}' | '\u{bf}'
+            | '\u{2010}'..='\u{2027}'
+            | '\u{2030}'..='\u{205e}'
+            | '\u{2e00}'..='\u{2e7f}'
+            | '\u{3001}'..='\u{3003}'
+            | '\u{3008}'..='\u{3011}'
+            | '\u{3014}'..='\u{301f}'
+            | '\u{ff01}'..='\u{ff0f}'
+            | '\u{ff1a}'..='\u{ff20}'
+            | '\u{ff3b}'..='\u{ff40}'
+            | '\u{ff5b}'..='\u{ff65}')
+}
+
+fn is_special(c: char) -> bool {
+    matches!(
+        c,
+        '`' | '*' | '_' | '~' | '[' | ']' | '!' | '^' | '<' | '\\' | '&' | '\n'
+    )
+}
+
+fn backticks(i: &str) -> IResult<&str, &str> {
+    take_while1(|c| c == '`')(i)
+}
+
+/// A run of `*` or `_` characters that may open and/or close emphasis, or of `~` for strikethrough.
+#[derive(Debug)]
+struct Delimiter {
+    character: char,
+    // number of characters still available for matching
+    count: usize,
+    // length of the run as found in the source, used by the "rule of 3"
+    original: usize,
+    can_open: bool,
+    can_close: bool,
+    // position in the order delimiters were found
+    id: usize,
+    // offset in the subject of the first character still available
+    start: usize,
+}
+
+impl Delimiter {
+    fn text(&self) -> String {
+        self.character.to_string().repeat(self.count)
+    }
+}
+
+// What an opening bracket may start.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BracketKind {
+    Link,
+    Image,
+    // an inline footnote
+    Footnote,
+}
+
+impl BracketKind {
+    fn text(self) -> &'static str {
+        match self {
+            BracketKind::Link => "[",
+            BracketKind::Image => "![",
+            BracketKind::Footnote => "^[",
+        }
+    }
+}
+
+enum LinkTarget {
+    Resource {
+        url: String,
+        title: Option<String>,
+        raw_url: Option<String>,
+        raw_title: Option<String>,
+    },
+    Reference {
+        reference_type: ReferenceType,
+        label: String,
+    },
+}
+
+// Offsets are byte offsets into the subject, located in the source once nodes are built.
+#[derive(Debug)]
+enum Inline {
+    Text {
+        value: String,
+        start: usize,
+        end: usize,
+    },
+    Delimiter(Delimiter),
+    // an opening `[`, `![` or `^[` that may start a link, image or inline footnote,
+    // inactive once it can no longer do so
+    Bracket {
+        kind: BracketKind,
+        active: bool,
+        // position in the subject right after the bracket
+        start: usize,
+        // whether another bracket was opened after this one
+        bracket_after: bool,
+    },
+    Node(PhrasingContent),
+}
+
+// `source` is the text as found in the subject, kept when escapes or references make it differ from the value.
+fn text(value: String, source: &str, position: Option<Position>) -> StaticPhrasingContent {
+    StaticPhrasingContent::Text(Text {
+        raw: raw(source, &value),
+        value: Some(value.into_bytes()),
+        position,
+    })
+}
+
+// Turns the remaining inlines into phrasing content, treating unmatched delimiters as text
+// and merging adjacent text.
+fn into_phrasing(inlines: Vec<Inline>, subject: &str, locator: &Locator) -> Vec<PhrasingContent> {
+    let text = |value, start, end| {
+        PhrasingContent::StaticPhrasingContent(text(
+            value,
+            &subject[start..end],
+            locator.position(start, end),
+        ))
+    };
+    let mut children = Vec::new();
+    // text waiting to be merged with the text that follows, with its start and end
+    let mut pending: Option<(String, usize, usize)> = None;
+    for inline in inlines {
+        let (value, start, end) = match inline {
+            Inline::Text { value, start, end } => (value, start, end),
+            Inline::Delimiter(delimiter) => (
+                delimiter.text(),
+                delimiter.start,
+                delimiter.start + delimiter.count,
+            ),
+            Inline::Bracket { kind, start, .. } => {
+                let value = kind.text();
+                (value.to_string(), start - value.len(), start)
+            }
+            Inline::Node(node) => {
+                if let Some((value, start, end)) = pending.take() {
+                    children.push(text(value, start, end));
+                }
+                children.push(node);
+                continue;
+            }
+        };
+        match &mut pending {
+            Some((pending, _, pending_end)) => {
+                pending.push_str(&value);
+                *pending_end = end;
+            }
+            None => pending = Some((value, start, end)),
+        }
+    }
+    if let Some((value, start, end)) = pending {
+        children.push(text(value, start, end));
+    }
+    children
+}
+
+// Link text may contain any phrasing content except other links, which the bracket handling already prevents.
+fn into_static_phrasing(children: Vec<PhrasingContent>) -> Vec<StaticPhrasingContent> {
+    children
+        .into_iter()
+        .flat_map(|child| match child {
+            PhrasingContent::Link(link) => link.children,
+            PhrasingContent::LinkReference(reference) => reference.children,
+            PhrasingContent::StaticPhrasingContent(content) => vec![content],
+        })
+        .collect()
+}
+
+fn plain_text_static(content: &StaticPhrasingContent, out: &mut String) {
+    match content {
+        StaticPhrasingContent::Break(_) => out.push('\n'),
+        StaticPhrasingContent::Emphasis(Emphasis { children, .. })
+        | StaticPhrasingContent::Strong(Strong { children, .. })
+        | StaticPhrasingContent::Delete(Delete { children, .. }) => plain_text(children, out),
+        StaticPhrasingContent::HTML(html) => out.push_str(&String::from_utf8_lossy(&html.value)),
+        StaticPhrasingContent::Image(image) => {
+            out.push_str(image.alternative.alt.as_deref().unwrap_or_default())
+        }
+        StaticPhrasingContent::ImageReference(image) => {
+            out.push_str(image.alternative.alt.as_deref().unwrap_or_default())
+        }
+        StaticPhrasingContent::InlineCode(code) => {
+            out.push_str(&String::from_utf8_lossy(&code.value))
+        }
+        StaticPhrasingContent::Text(text) => out.push_str(&String::from_utf8_lossy(
+            text.value.as_deref().unwrap_or_default(),
+        )),
+        // notes are not part of the text they annotate
+        StaticPhrasingContent::Footnote(_) | StaticPhrasingContent::FootnoteReference(_) => {}
+    }
+}
+
+// The textual content of phrasing content without any markup, used as the alt of images.
+fn plain_text(children: &[PhrasingContent], out: &mut String) {
+    for child in children {
+        match child {
+            PhrasingContent::Link(Link { children, .. })
+            | PhrasingContent::LinkReference(LinkReference { children, .. }) => {
+                for content in children {
+                    plain_text_static(content, out);
+                }
+            }
+            PhrasingContent::StaticPhrasingContent(content) => plain_text_static(content, out),
+        }
+    }
+}
+
+fn delimiter_mut(inlines: &mut [Inline], index: usize) -> &mut Delimiter {
+    match &mut inlines[index] {
+        Inline::Delimiter(delimiter) => delimiter,
+        other => unreachable!("expected a delimiter, found {:?}", other),
+    }
+}
+
+// Matches delimiter runs after `bottom` (an index into `inlines`) into emphasis, strong and delete nodes.
+// Runs of tildes only match runs of the same length.
+// https://spec.commonmark.org/0.30/#process-emphasis
+fn process_emphasis(inlines: &mut Vec<Inline>, bottom: usize, subject: &str, locator: &Locator) {
+    // lowest delimiter id an opener may have, per closer character, whether the closer can also open,
+    // and the length of the closer modulo 3
+    let mut openers_bottom = [[0usize; 6]; 3];
+
+    let mut current = bottom;
+    while current < inlines.len() {
+        let (character, can_open, original, id) = match &inlines[current] {
+            Inline::Delimiter(delimiter) if delimiter.can_close => (
+                delimiter.character,
+                delimiter.can_open,
+                delimiter.original,
+                delimiter.id,
+            ),
+            _ => {
+                current += 1;
+                continue;
+            }
+        };
+
+        let slot = (
+            match character {
+                '*' => 0,
+                '_' => 1,
+                _ => 2,
+            },
+            if can_open { 3 } else { 0 } + original % 3,
+        );
+        let lowest = openers_bottom[slot.0][slot.1];
+
+        let mut opener_index = None;
+        let mut index = current;
+        while index > bottom {
+            index -= 1;
+            if let Inline::Delimiter(opener) = &inlines[index] {
+                if opener.id < lowest {
+                    break;
+                }
+                // if one of the delimiters can both open and close emphasis, the sum of the lengths of the
+                // delimiter runs containing the opening and closing delimiters must not be a multiple of 3
+                // unless both lengths are multiples of 3
+                let odd_match = (opener.can_close || can_open)
+                    && (opener.original + original) % 3 == 0
+                    && !(opener.original % 3 == 0 && original % 3 == 0);
+                let matches = if character == '~' {
+                    opener.original == original
+                } else {
+                    !odd_match
+                };
+                if opener.character == character && opener.can_open && matches {
+                    opener_index = Some(index);
+                    break;
+                }
+            }
+        }
+
+        let opener_index = match opener_index {
+            Some(index) => index,
+            None => {
+                openers_bottom[slot.0][slot.1] = id;
+                if !can_open {
+                    // can no longer take part in emphasis so keep it as plain text
+                    let delimiter = delimiter_mut(inlines, current);
+                    inlines[current] = Inline::Text {
+                        value: delimiter.text(),
+                        start: delimiter.start,
+                        end: delimiter.start + delimiter.count,
+                    };
+                }
+                current += 1;
+                continue;
+            }
+        };
+
+        let used = if character == '~' {
+            original
+        } else if delimiter_mut(inlines, current).count >= 2
+            && delimiter_mut(inlines, opener_index).count >= 2
+        {
+            2
+        } else {
+            1
+        };
+        // openers give up their last characters and closers their first ones
+        let opener = delimiter_mut(inlines, opener_index);
+        opener.count -= used;
+        let start = opener.start + opener.count;
+        let closer = delimiter_mut(inlines, current);
+        closer.count -= used;
+        closer.start += used;
+        let position = locator.position(start, closer.start);
+
+        let children = into_phrasing(
+            inlines.drain(opener_index + 1..current).collect(),
+            subject,
+            locator,
+        );
+        let node = if character == '~' {
+            StaticPhrasingContent::Delete(Delete { children, position })
+        } else if used == 2 {
+            StaticPhrasingContent::Strong(Strong { children, position })
+        } else {
+            StaticPhrasingContent::Emphasis(Emphasis { children, position })
+        };
+        inlines.insert(
+            opener_index + 1,
+            Inline::Node(PhrasingContent::StaticPhrasingContent(node)),
+        );
+
+        current = opener_index + 2;
+        if delimiter_mut(inlines, opener_index).count == 0 {
+            inlines.remove(opener_index);
+            current -= 1;
+        }
+        if delimiter_mut(inlines, current).count == 0 {
+            inlines.remove(current);
+        }
+    }
+}
+
+/// What references in the inline content of a document resolve to, collected by the block parser.
+#[derive(Debug, Default)]
+pub(crate) struct Definitions {
+    /// Normalized labels of the link reference definitions.
+    pub(crate) links: HashSet<String>,
+    /// Normalized labels of the footnote definitions.
+    pub(crate) footnotes: HashSet<String>,
+}
+
+struct InlineParser<'a> {
+    subject: &'a str,
+    options: &'a Options,
+    definitions: &'a Definitions,
+    locator: &'a Locator<'a>,
+    pos: usize,
+    inlines: Vec<Inline>,
+    delimiters: usize,
+    // where autolink literals were left as text because a link or image opener was waiting
+    suppressed_literals: Vec<usize>,
+    // link and image openers, by the position after them, known not to start a link or image
+    failed_openers: HashSet<usize>,
+}
+
+impl<'a> InlineParser<'a> {
+    fn new(
+        subject: &'a str,
+        options: &'a Options,
+        definitions: &'a Definitions,
+        locator: &'a Locator<'a>,
+    ) -> InlineParser<'a> {
+        InlineParser {
+            subject,
+            options,
+            definitions,
+            locator,
+            pos: 0,
+            inlines: Vec::new(),
+            delimiters: 0,
+            suppressed_literals: Vec::new(),
+            failed_openers: HashSet::new(),
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.subject[self.pos..]
+    }
+
+    // Adds text that ends at the current position.
+    fn push_text(&mut self, value: &str, start: usize) {
+        if let Some(Inline::Text {
+            value: last, end, ..
+        }) = self.inlines.last_mut()
+        {
+            last.push_str(value);
+            *end = self.pos;
+        } else {
+            self.inlines.push(Inline::Text {
+                value: value.to_string(),
+                start,
+                end: self.pos,
+            });
+        }
+    }
+
+    // Adds the character at the current position as text.
+    fn push_char(&mut self) {
+        let start = self.pos;
+        self.pos += self.rest().chars().next().map_or(0, char::len_utf8);
+        self.push_text(&self.subject[start..self.pos], start);
+    }
+
+    // Adds a link that started at `start` and whose only child is `value`, found between `text_start`
+    // and `text_end`.
+    fn push_autolink(
+        &mut self,
+        url: String,
+        value: String,
+        start: usize,
+        text_start: usize,
+        text_end: usize,
+    ) {
+        let children = vec![text(
+            value,
+            &self.subject[text_start..text_end],
+            self.locator.position(text_start, text_end),
+        )];
+        self.inlines.push(Inline::Node(PhrasingContent::Link(Link {
+            url,
+            title: None,
+            raw_url: None,
+            raw_title: None,
+            children,
+            position: self.locator.position(start, self.pos),
+        })));
+    }
+
+    // Whether a link or image opener is waiting for its closing bracket. An autolink literal found meanwhile
+    // is recorded, so that the text can be read again should the opener not start a link after all.
+    fn in_link_text(&mut self) -> bool {
+        let failed_openers = &self.failed_openers;
+        let waiting = self.inlines.iter().any(|inline| match inline {
+            Inline::Bracket {
+                kind: BracketKind::Link | BracketKind::Image,
+                start,
+                ..
+            } => !failed_openers.contains(start),
+            _ => false,
+        });
+        if waiting {
+            self.suppressed_literals.push(self.pos);
+        }
+        waiting
+    }
+
+    // Reads everything from the link or image opener at index `opener` of the inlines again when it does
+    // not start a link or image and autolink literals were left as text after it. The opener still
+    // matches the same closing bracket but no longer counts as link text.
+    fn reread_after(&mut self, opener: usize) -> bool {
+        let (kind, start) = match self.inlines[opener] {
+            Inline::Bracket {
+                kind: kind @ (BracketKind::Link | BracketKind::Image),
+                start,
+                ..
+            } => (kind, start),
+            _ => return false,
+        };
+        if self.failed_openers.contains(&start)
+            || !self
+                .suppressed_literals
+                .iter()
+                .any(|&literal| literal > start)
+        {
+            return false;
+        }
+        self.failed_openers.insert(start);
+        self.suppressed_literals.retain(|&literal| literal < start);
+        self.inlines.truncate(opener);
+        self.pos = start - kind.text().len();
+        true
+    }
+
+    fn parse(mut self) -> Vec<PhrasingContent> {
+        loop {
+            self.parse_subject();
+            // openers left at the end never start a link
+            if !(0..self.inlines.len()).any(|opener| self.reread_after(opener)) {
+                break;
+            }
+        }
+
+        process_emphasis(&mut self.inlines, 0, self.subject, self.locator);
+        into_phrasing(self.inlines, self.subject, self.locator)
+    }
+
+    fn parse_subject(&mut self) {
+        while let Some(c) = self.rest().chars().next() {
+            match c {
+                '`' => self.parse_code_span(),
+                '*' | '_' => self.parse_delimiter_run(c),
+                '~' if self.options.strikethrough => self.parse_delimiter_run(c),
+                '[' if self.options.footnotes && self.rest().starts_with("[^") => {
+                    self.parse_footnote_reference()
+                }
+                '[' => self.push_bracket(BracketKind::Link),
+                '!' if self.rest().starts_with("![") => self.push_bracket(BracketKind::Image),
+                '^' if self.options.footnotes && self.rest().starts_with("^[") => {
+                    self.push_bracket(BracketKind::Footnote)
+                }
+                ']' => self.parse_close_bracket(),
+                '<' => self.parse_pointy_brace(),
+                '\\' => self.parse_backslash(),
+                '&' => self.parse_character_reference(),
+                '\n' => self.parse_line_ending(),
+                'w' | 'h' if self.options.autolink_literals => self.parse_autolink_literal(),
+                '@' if self.options.autolink_literals => self.parse_email_literal(),
+                '!' | '~' | '^' => self.push_char(),
+                _ => {
+                    let rest = self.rest();
+                    let literals = self.options.autolink_literals;
+                    let len = rest
+                        .find(|c| is_special(c) || literals && matches!(c, 'w' | 'h' | '@'))
+                        .unwrap_or(rest.len());
+                    self.pos += len;
+                    self.push_text(&rest[..len], self.pos - len);
+                }
+            }
+        }
+    }
+
+    fn push_break(&mut self, start: usize) {
+        self.inlines
+            .push(Inline::Node(PhrasingContent::StaticPhrasingContent(
+                StaticPhrasingContent::Break(Break {
+                    position: self.locator.position(start, self.pos),
+                }),
+            )));
+    }
+
+    // Any ASCII punctuation character may be backslash-escaped to be taken literally.
+    // A backslash at the end of a line is a hard line break.
+    // https://spec.commonmark.org/0.30/#backslash-escapes
+    fn parse_backslash(&mut self) {
+        let start = self.pos;
+        match self.rest()[1..].chars().next() {
+            Some('\n') => {
+                self.pos += 2;
+                self.push_break(start);
+            }
+            Some(escaped) if escaped.is_ascii_punctuation() => {
+                self.pos += 2;
+                self.push_text(&escaped.to_string(), start);
+            }
+            _ => self.push_char(),
+        }
+    }
+
+    // A line ending preceded by two or more spaces is a hard line break. Any other line ending is a soft
+    // line break, kept in the text, and the spaces or tabs at the end of the line are removed either way.
+    // https://spec.commonmark.org/0.30/#hard-line-breaks
+    fn parse_line_ending(&mut self) {
+        let line = &self.subject[..self.pos];
+        let hard = line.ends_with("  ");
+        let start = line.trim_end_matches([' ', '\t']).len();
+        if let Some(Inline::Text { value, end, .. }) = self.inlines.last_mut() {
+            if *end == self.pos {
+                let trimmed = value.trim_end_matches([' ', '\t']).len();
+                value.truncate(trimmed);
+                *end = start;
+                if value.is_empty() {
+                    self.inlines.pop();
+                }
+            }
+        }
+        self.pos += 1;
+        if hard {
+            self.push_break(start);
+        } else {
+            self.push_text("\n", self.pos - 1);
+        }
+    }
+
+    fn parse_character_reference(&mut self) {
+        match character_reference(self.rest()) {
+            Ok((rest, decoded)) => {
+                let start = self.pos;
+                self.pos = self.subject.len() - rest.len();
+                self.push_text(&decoded, start);
+            }
+            Err(_) => self.push_char(),
+        }
+    }
+
+    // A code span begins with a backtick string and ends with a backtick string of equal length.
+    // https://spec.commonmark.org/0.30/#code-spans
+    fn parse_code_span(&mut self) {
+        let (after, opening) = match backticks(self.rest()) {
+            Ok(result) => result,
+            Err(_) => return,
+        };
+        let start = self.pos;
+        self.pos += opening.len();
+
+        let mut search = after;
+        while let Some(found) = search.find('`') {
+            let (remaining, closing) = match backticks(&search[found..]) {
+                Ok(result) => result,
+                Err(_) => break,
+            };
+            if closing.len() == opening.len() {
+                let content = &after[..after.len() - search.len() + found];
+                self.pos += content.len() + closing.len();
+                self.inlines
+                    .push(Inline::Node(PhrasingContent::StaticPhrasingContent(
+                        StaticPhrasingContent::InlineCode(InlineCode {
+                            value: code_span_content(content).into_bytes(),
+                            position: self.locator.position(start, self.pos),
+                        }),
+                    )));
+                return;
+            }
+            search = remaining;
+        }
+
+        // no matching closer so the backticks are literal
+        self.push_text(opening, start);
+    }
+
+    // An autolink or raw HTML, which is kept as written.
+    // https://spec.commonmark.org/0.30/#autolinks
+    // https://spec.commonmark.org/0.30/#raw-html
+    fn parse_pointy_brace(&mut self) {
+        let start = self.pos;
+        if let Ok((rest, uri)) = uri_autolink(self.rest()) {
+            self.pos = self.subject.len() - rest.len();
+            let uri = decode_references(uri);
+            self.push_autolink(uri.clone(), uri, start, start + 1, self.pos - 1);
+        } else if let Ok((rest, email)) = email_autolink(self.rest()) {
+            self.pos = self.subject.len() - rest.len();
+            let email = decode_references(email);
+            let url = format!("mailto:{}", email);
+            self.push_autolink(url, email, start, start + 1, self.pos - 1);
+        } else if let Ok((rest, html)) = html_tag(self.rest()) {
+            self.pos = self.subject.len() - rest.len();
+            self.inlines
+                .push(Inline::Node(PhrasingContent::StaticPhrasingContent(
+                    StaticPhrasingContent::HTML(HTML {
+                        value: html.as_bytes().to_vec(),
+                        position: self.locator.position(start, self.pos),
+                    }),
+                )));
+        } else {
+            self.push_char();
+        }
+    }
+
+    // Extended www and url autolinks only start at the beginning of a line, after whitespace or after one of
+    // `*`, `_`, `~` and `(`. Like all extended autolinks they are not recognized within link text.
+    fn parse_autolink_literal(&mut self) {
+        let start = self.pos;
+        let before = self.subject[..start].chars().next_back().unwrap_or('\n');
+        let boundary = before.is_whitespace() || matches!(before, '*' | '_' | '~' | '(');
+        let found = if !boundary {
+            None
+        } else if let Ok((_, link)) = www_autolink(self.rest()) {
+            Some((format!("http://{}", link), link.len()))
+        } else if let Ok((_, link)) = url_autolink(self.rest()) {
+            Some((link.to_string(), link.len()))
+        } else {
+            None
+        };
+        match found.filter(|_| !self.in_link_text()) {
+            Some((url, len)) => {
+                self.pos += len;
+                let link = self.subject[start..self.pos].to_string();
+                self.push_autolink(url, link, start, start, self.pos);
+            }
+            None => self.push_char(),
+        }
+    }
+
+    // The local part of an extended email autolink has already been added as text by the time its `@` is
+    // found so it is taken back from the end of that text.
+    fn parse_email_literal(&mut self) {
+        let before = &self.subject[..self.pos];
+        let start = before.trim_end_matches(is_email_local).len();
+        let local = &before[start..];
+        let email = match email_autolink_literal(&self.subject[start..]) {
+            Ok((_, email)) if !self.in_link_text() => email,
+            _ => return self.push_char(),
+        };
+        match self.inlines.last_mut() {
+            Some(Inline::Text {
+                value,
+                start: text_start,
+                end,
+            }) if *end == self.pos && *text_start <= start && value.ends_with(local) => {
+                value.truncate(value.len() - local.len());
+                *end = start;
+                if value.is_empty() {
+                    self.inlines.pop();
+                }
+            }
+            _ => return self.push_char(),
+        }
+        self.pos = start + email.len();
+        self.push_autolink(
+            format!("mailto:{}", email),
+            email.to_string(),
+            start,
+            start,
+            self.pos,
+        );
+    }
+
+    // A footnote label makes a reference when a footnote definition matches it, like in GFM. Anything else
+    // starting with `[^` is an ordinary bracket, which Root::footnotes finds in the text to report it.
+    fn parse_footnote_reference(&mut self) {
+        let (rest, label) = match footnote_label(self.rest()) {
+            Ok(result) => result,
+            Err(_) => return self.push_bracket(BracketKind::Link),
+        };
+        let association = Association::new(label);
+        if !self.definitions.footnotes.contains(&association.identifier) {
+            return self.push_bracket(BracketKind::Link);
+        }
+        let start = self.pos;
+        self.pos = self.subject.len() - rest.len();
+        let reference = FootnoteReference {
+            association,
+            position: self.locator.position(start, self.pos),
+        };
+        self.inlines
+            .push(Inline::Node(PhrasingContent::StaticPhrasingContent(
+                StaticPhrasingContent::FootnoteReference(reference),
+            )));
+    }
+
+    fn push_bracket(&mut self, kind: BracketKind) {
+        if let Some(Inline::Bracket { bracket_after, .. }) = self
+            .inlines
+            .iter_mut()
+            .rev()
+            .find(|inline| matches!(inline, Inline::Bracket { .. }))
+        {
+            *bracket_after = true;
+        }
+        self.pos += kind.text().len();
+        self.inlines.push(Inline::Bracket {
+            kind,
+            active: true,
+            start: self.pos,
+            bracket_after: false,
+        });
+    }
+
+    // Looks for a link or image opened by the closest bracket, or closes an inline footnote.
+    // https://spec.commonmark.org/0.30/#look-for-link-or-image
+    fn parse_close_bracket(&mut self) {
+        let close = self.pos;
+        self.pos += 1;
+
+        let opener = self
+            .inlines
+            .iter()
+            .rposition(|inline| matches!(inline, Inline::Bracket { .. }));
+        let (opener, kind, active, start, bracket_after) =
+            match opener.map(|index| (index, &self.inlines[index])) {
+                Some((
+                    index,
+                    Inline::Bracket {
+                        kind,
+                        active,
+                        start,
+                        bracket_after,
+                    },
+                )) => (index, *kind, *active, *start, *bracket_after),
+                _ => {
+                    self.push_text("]", close);
+                    return;
+                }
+            };
+
+        if kind == BracketKind::Footnote {
+            process_emphasis(&mut self.inlines, opener + 1, self.subject, self.locator);
+            let children = into_phrasing(
+                self.inlines.drain(opener + 1..).collect(),
+                self.subject,
+                self.locator,
+            );
+            self.inlines.pop();
+            let position = self.locator.position(start - kind.text().len(), self.pos);
+            self.inlines
+                .push(Inline::Node(PhrasingContent::StaticPhrasingContent(
+                    StaticPhrasingContent::Footnote(Footnote { children, position }),
+                )));
+            return;
+        }
+
+        let target = if active {
+            self.link_target(&self.subject[start..close], bracket_after)
+        } else {
+            None
+        };
+        let target = match target {
+            Some(target) => target,
+            None if self.reread_after(opener) => return,
+            None => {
+                // the opener can no longer start a link so it is literal text from now on
+                let value = kind.text();
+                self.inlines[opener] = Inline::Text {
+                    value: value.to_string(),
+                    start: start - value.len(),
+                    end: start,
+                };
+                self.push_text("]", close);
+                return;
+            }
+        };
+
+        process_emphasis(&mut self.inlines, opener + 1, self.subject, self.locator);
+        let children = into_phrasing(
+            self.inlines.drain(opener + 1..).collect(),
+            self.subject,
+            self.locator,
+        );
+        self.inlines.pop();
+        let position = self.locator.position(start - kind.text().len(), self.pos);
+
+        let node = if kind == BracketKind::Image {
+            let mut alt = String::new();
+            plain_text(&children, &mut alt);
+            let alternative = Alternative { alt: Some(alt) };
+            PhrasingContent::StaticPhrasingContent(match target {
+                LinkTarget::Resource {
+                    url,
+                    title,
+                    raw_url,
+                    raw_title,
+                } => StaticPhrasingContent::Image(Image {
+                    url,
+                    title,
+                    raw_url,
+                    raw_title,
+                    alternative,
+                    position,
+                }),
+                LinkTarget::Reference {
+                    reference_type,
+                    label,
+                } => StaticPhrasingContent::ImageReference(ImageReference {
+                    reference: Reference { reference_type },
+                    association: Association::new(&label),
+                    alternative,
+                    position,
+                }),
+            })
+        } else {
+            // links may not contain other links so earlier link openers are deactivated
+            for inline in &mut self.inlines {
+                if let Inline::Bracket {
+                    kind: BracketKind::Link,
+                    active,
+                    ..
+                } = inline
+                {
+                    *active = false;
+                }
+            }
+            let children = into_static_phrasing(children);
+            match target {
+                LinkTarget::Resource {
+                    url,
+                    title,
+                    raw_url,
+                    raw_title,
+                } => PhrasingContent::Link(Link {
+                    url,
+                    title,
+                    raw_url,
+                    raw_title,
+                    children,
+                    position,
+                }),
+                LinkTarget::Reference {
+                    reference_type,
+                    label,
+                } => PhrasingContent::LinkReference(LinkReference {
+                    reference: Reference { reference_type },
+                    association: Association::new(&label),
+                    children,
+                    position,
+                }),
+            }
+        };
+        self.inlines.push(Inline::Node(node));
+    }
+
+    // Parses what follows the link text of a potential link: an inline destination and title or a
+    // reference to a definition. Only consumes input when a link is found.
+    // `text` is the raw link text which doubles as the label of collapsed and shortcut references
+    // unless it contains other brackets.
+    fn link_target(&mut self, text: &str, bracket_after: bool) -> Option<LinkTarget> {
+        if let Ok((rest, (destination, title))) = inline_link(self.rest()) {
+            self.pos = self.subject.len() - rest.len();
+            let (url, raw_url) = unescape_raw(destination);
+            let (title, raw_title) = unescape_raw_option(title);
+            return Some(LinkTarget::Resource {
+                url,
+                title,
+                raw_url,
+                raw_title,
+            });
+        }
+
+        let (rest, reference_type, label) = if let Ok((rest, label)) = link_label(self.rest()) {
+            (rest, ReferenceType::Full, label)
+        } else if let Some(rest) = self.rest().strip_prefix("[]") {
+            (rest, ReferenceType::Collapsed, text)
+        } else {
+            (self.rest(), ReferenceType::Shortcut, text)
+        };
+        if reference_type != ReferenceType::Full && (bracket_after || text.chars().count() > 999) {
+            return None;
+        }
+        if !self
+            .definitions
+            .links
+            .contains(&Association::normalize(label))
+        {
+            return None;
+        }
+
+        self.pos = self.subject.len() - rest.len();
+        Some(LinkTarget::Reference {
+            reference_type,
+            label: label.to_string(),
+        })
+    }
+
+    // https://spec.commonmark.org/0.30/#delimiter-run
+    fn parse_delimiter_run(&mut self, character: char) {
+        let start = self.pos;
+        let length = self.rest().len() - self.rest().trim_start_matches(character).len();
+        self.pos += length;
+
+        let before = self.subject[..start].chars().next_back().unwrap_or('\n');
+        let after = self.rest().chars().next().unwrap_or('\n');
+
+        let before_whitespace = before.is_whitespace();
+        let before_punctuation = is_punctuation(before);
+        let after_whitespace = after.is_whitespace();
+        let after_punctuation = is_punctuation(after);
+
+        let left_flanking =
+            !after_whitespace && (!after_punctuation || before_whitespace || before_punctuation);
+        let right_flanking =
+            !before_whitespace && (!before_punctuation || after_whitespace || after_punctuation);
+
+        // strikethrough takes one or two tildes, or only two unless a single tilde is enabled
+        let tildes = if self.options.single_tilde {
+            1..=2
+        } else {
+            2..=2
+        };
+        if character == '~' && !tildes.contains(&length) {
+            self.push_text(&self.subject[start..self.pos], start);
+            return;
+        }
+
+        let (can_open, can_close) = if character == '_' {
+            (
+                left_flanking && (!right_flanking || before_punctuation),
+                right_flanking && (!left_flanking || after_punctuation),
+            )
+        } else {
+            (left_flanking, right_flanking)
+        };
+
+        if !can_open && !can_close {
+            self.push_text(&self.subject[start..self.pos], start);
+            return;
+        }
+
+        self.inlines.push(Inline::Delimiter(Delimiter {
+            character,
+            count: length,
+            original: length,
+            can_open,
+            can_close,
+            id: self.delimiters,
+            start,
+        }));
+        self.delimiters += 1;
+    }
+}
+
+// Line endings are converted to spaces and, if the content both begins and ends with a space
+// but is not only spaces, a single space is removed from each side.
+fn code_span_content(content: &str) -> String {
+    let content = content.replace('\n', " ");
+    if content.len() >= 2
+        && content.starts_with(' ')
+        && content.ends_with(' ')
+        && !content.chars().all(|c| c == ' ')
+    {
+        return content[1..content.len() - 1].to_string();
+    }
+    content
+}
+
+/// Parses the raw content of a paragraph or heading into phrasing content.
+/// `options` tells which extensions to recognize, `definitions` holds the labels of the link reference
+/// and footnote definitions in the document and `locator` places the content in the source.
+/// Anything that is not recognized as markup is kept as text so this never fails.
+pub(crate) fn parse_inlines(
+    i: &str,
+    options: &Options,
+    definitions: &Definitions,
+    locator: &Locator,
+) -> Vec<PhrasingContent> {
+    InlineParser::new(i, options, definitions, locator).parse()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::position::{LineIndex, SourceMap};
+    use super::*;
+
+    fn parse_with_definitions(i: &str, definitions: &Definitions) -> Vec<PhrasingContent> {
+        let lines = LineIndex::new(i);
+        let map = SourceMap::new(0, 0);
+        super::parse_inlines(
+            i,
+            &Options::default(),
+            definitions,
+            &Locator {
+                lines: &lines,
+                map: &map,
+            },
+        )
+    }
+
+    fn parse_inlines(i: &str) -> Vec<PhrasingContent> {
+        parse_with_definitions(i, &Definitions::default())
+    }
+
+    // renders phrasing content in a compact form to keep assertions readable
+    fn outline(content: &[PhrasingContent]) -> String {
+        content
+            .iter()
+            .map(|child| match child {
+                PhrasingContent::Link(l) => format!(
+                    "link({}, {:?}, {})",
+                    l.url,
+                    l.title,
+                    l.children.iter().map(outline_static).collect::<String>()
+                ),
+                PhrasingContent::LinkReference(l) => format!(
+                    "linkReference({:?}, {}, {})",
+                    l.reference.reference_type,
+                    l.association.identifier,
+                    l.children.iter().map(outline_static).collect::<String>()
+                ),
+                PhrasingContent::StaticPhrasingContent(content) => outline_static(content),
+            })
+            .collect()
+    }
+
+    fn outline_static(content: &StaticPhrasingContent) -> String {
+        match content {
+            StaticPhrasingContent::Text(text) => {
+                String::from_utf8(text.value.clone().unwrap_or_default()).unwrap()
+            }
+            StaticPhrasingContent::Emphasis(e) => format!("em({})", outline(&e.children)),
+            StaticPhrasingContent::Strong(s) => format!("strong({})", outline(&s.children)),
+            StaticPhrasingContent::Delete(d) => format!("delete({})", outline(&d.children)),
+            StaticPhrasingContent::Footnote(f) => format!("footnote({})", outline(&f.children)),
+            StaticPhrasingContent::FootnoteReference(f) => {
+                format!("footnoteReference({})", f.association.identifier)
+            }
+            StaticPhrasingContent::InlineCode(c) => {
+                format!("code({})", String::from_utf8(c.value.clone()).unwrap())
+            }
+            StaticPhrasingContent::Break(_) => "break".to_string(),
+            StaticPhrasingContent::HTML(h) => {
+                format!("html({})", String::from_utf8(h.value.clone()).unwrap())
+            }
+            StaticPhrasingContent::Image(i) => format!(
+                "image({}, {:?}, {})",
+                i.url,
+                i.title,
+                i.alternative.alt.as_deref().unwrap_or_default()
+            ),
+            StaticPhrasingContent::ImageReference(i) => format!(
+                "imageReference({:?}, {}, {})",
+                i.reference.reference_type,
+                i.association.identifier,
+                i.alternative.alt.as_deref().unwrap_or_default()
+            ),
+        }
+    }
+
+    #[test]
+    fn emphasis() {
+        assert_eq!(outline(&parse_inlines("*alpha*")), "em(alpha)");
+        assert_eq!(outline(&parse_inlines("_alpha_")), "em(alpha)");
+        assert_eq!(outline(&parse_inlines("*foo*bar")), "em(foo)bar");
+        assert_eq!(outline(&parse_inlines("_foo_bar")), "_foo_bar");
+        assert_eq!(outline(&parse_inlines("a * foo bar*")), "a * foo bar*");
+        assert_eq!(outline(&parse_inlines("*(*foo*)*")), "em((em(foo)))");
+
+        let serialized = serde_json::to_string(&parse_inlines("*alpha*")).unwrap();
+        assert!(serialized.contains(r#""type":"emphasis""#));
+    }
+
+    #[test]
+    fn strong() {
+        assert_eq!(outline(&parse_inlines("**alpha**")), "strong(alpha)");
+        assert_eq!(outline(&parse_inlines("__alpha__")), "strong(alpha)");
+        assert_eq!(outline(&parse_inlines("**foo*")), "*em(foo)");
+        assert_eq!(outline(&parse_inlines("*foo**")), "em(foo)*");
+    }
+
+    #[test]
+    fn nesting() {
+        assert_eq!(
+            outline(&parse_inlines("***bold italic***")),
+            "em(strong(bold italic))"
+        );
+        assert_eq!(
+            outline(&parse_inlines("**foo *bar* baz**")),
+            "strong(foo em(bar) baz)"
+        );
+        assert_eq!(
+            outline(&parse_inlines("*foo**bar**baz*")),
+            "em(foostrong(bar)baz)"
+        );
+        // rule of 3
+        assert_eq!(outline(&parse_inlines("*foo**bar*")), "em(foo**bar)");
+        assert_eq!(
+            outline(&parse_inlines("foo***bar***baz")),
+            "fooem(strong(bar))baz"
+        );
+    }
+
+    #[test]
+    fn strikethrough() {
+        assert_eq!(
+            outline(&parse_inlines("~~Hi~~ Hello, ~there~ world!")),
+            "delete(Hi) Hello, delete(there) world!"
+        );
+        assert_eq!(
+            outline(&parse_inlines("This will ~~~not~~~ strike.")),
+            "This will ~~~not~~~ strike."
+        );
+        // only runs of the same length match
+        assert_eq!(outline(&parse_inlines("~~a ~b~")), "~~a delete(b)");
+        assert_eq!(outline(&parse_inlines("a ~~ b~~")), "a ~~ b~~");
+        assert_eq!(
+            outline(&parse_inlines("*~~a*~~ ~~*b~~*")),
+            "em(~~a)~~ delete(*b)*"
+        );
+        assert_eq!(
+            outline(&parse_inlines("**~~a~~** ~~*b*~~")),
+            "strong(delete(a)) delete(em(b))"
+        );
+    }
+
+    #[test]
+    fn code_spans() {
+        assert_eq!(
+            outline(&parse_inlines("`` foo ` bar ``")),
+            "code(foo ` bar)"
+        );
+        assert_eq!(outline(&parse_inlines("*foo`*`")), "*foocode(*)");
+        assert_eq!(outline(&parse_inlines("```foo``")), "```foo``");
+    }
+
+    #[test]
+    fn links() {
+        assert_eq!(
+            outline(&parse_inlines("[link](/uri \"title\")")),
+            "link(/uri, Some(\"title\"), link)"
+        );
+        assert_eq!(
+            outline(&parse_inlines("a [*b* `c`](<d e>) f")),
+            "a link(d e, None, em(b) code(c)) f"
+        );
+        assert_eq!(
+            outline(&parse_inlines("[link [foo [bar]]](/uri)")),
+            "link(/uri, None, link [foo [bar]])"
+        );
+        // links may not contain other links
+        assert_eq!(
+            outline(&parse_inlines("[foo [bar](/uri)](/uri)")),
+            "[foo link(/uri, None, bar)](/uri)"
+        );
+        assert_eq!(
+            outline(&parse_inlines("*[foo*](/uri)")),
+            "*link(/uri, None, foo*)"
+        );
+        assert_eq!(outline(&parse_inlines("[link] (/uri)")), "[link] (/uri)");
+        assert_eq!(
+            outline(&parse_inlines("[not a `link](/foo`]")),
+            "[not a code(link](/foo)]"
+        );
+    }
+
+    #[test]
+    fn autolinks() {
+        assert_eq!(
+            outline(&parse_inlines("<https://a.b/c> and <me@a.b>")),
+            "link(https://a.b/c, None, https://a.b/c) and link(mailto:me@a.b, None, me@a.b)"
+        );
+        assert_eq!(
+            outline(&parse_inlines("<3 not an autolink>")),
+            "<3 not an autolink>"
+        );
+        assert_eq!(
+            outline(&parse_inlines("See www.a.b/c, *http://a.b*, or me+x@a.b.")),
+            "See link(http://www.a.b/c, None, www.a.b/c), em(link(http://a.b, None, http://a.b)), or link(mailto:me+x@a.b, None, me+x@a.b)."
+        );
+        // literals need a boundary before them and are not recognized in link text
+        assert_eq!(
+            outline(&parse_inlines("awww.a.b xhttp://a.b")),
+            "awww.a.b xhttp://a.b"
+        );
+        assert_eq!(
+            outline(&parse_inlines("[www.a.b me@a.b](/uri)")),
+            "link(/uri, None, www.a.b me@a.b)"
+        );
+        assert_eq!(
+            outline(&parse_inlines("[a [b www.a.b] *c*](/uri)")),
+            "link(/uri, None, a [b www.a.b] em(c))"
+        );
+        // unless the brackets do not make a link after all
+        assert_eq!(
+            outline(&parse_inlines("see [x and www.example.com")),
+            "see [x and link(http://www.example.com, None, www.example.com)"
+        );
+        assert_eq!(
+            outline(&parse_inlines("[*a* me@a.b ![b [c](/d) http://a.b")),
+            "[em(a) link(mailto:me@a.b, None, me@a.b) ![b link(/d, None, c) link(http://a.b, None, http://a.b)"
+        );
+
+        let content = parse_inlines("mail me@a.b");
+        let link = match &content[1] {
+            PhrasingContent::Link(link) => link,
+            other => panic!("expected link, got {:?}", other),
+        };
+        let position = link.position.unwrap();
+        assert_eq!(
+            (position.start.offset, position.end.offset),
+            (Some(5), Some(11))
+        );
+    }
+
+    #[test]
+    fn escapes_and_references() {
+        assert_eq!(
+            outline(&parse_inlines("\\*a\\* \\[b](c) &lt;d&gt; &#35;e \\q &x")),
+            "*a* [b](c) <d> #e \\q &x"
+        );
+        assert_eq!(
+            outline(&parse_inlines(
+                "`\\*` [a](/f&ouml;\\) \"&quot;\\!\") <http://a/\\&amp;>"
+            )),
+            "code(\\*) link(/fö), Some(\"\\\"!\"), a) link(http://a/\\&, None, http://a/\\&)"
+        );
+        assert_eq!(outline(&parse_inlines("&#42;a&#42;")), "*a*");
+
+        let content = parse_inlines("a &amp; b\\! *c*");
+        match &content[0] {
+            PhrasingContent::StaticPhrasingContent(StaticPhrasingContent::Text(text)) => {
+                assert_eq!(text.value.as_deref(), Some(&b"a & b! "[..]));
+                assert_eq!(text.raw.as_deref(), Some("a &amp; b\\! "));
+            }
+            other => panic!("expected text, got {:?}", other),
+        }
+        match &content[1] {
+            PhrasingContent::StaticPhrasingContent(StaticPhrasingContent::Emphasis(e)) => {
+                assert!(matches!(
+                    &e.children[0],
+                    PhrasingContent::StaticPhrasingContent(StaticPhrasingContent::Text(Text {
+                        raw: None,
+                        ..
+                    }))
+                ));
+            }
+            other => panic!("expected emphasis, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn line_breaks() {
+        assert_eq!(
+            outline(&parse_inlines("a  \nb\\\nc \nd")),
+            "abreakbbreakc\nd"
+        );
+        assert_eq!(
+            outline(&parse_inlines("*a  \nb* `c  \nd`")),
+            "em(abreakb) code(c   d)"
+        );
+        // a line ending is needed for a hard break
+        assert_eq!(outline(&parse_inlines("a\\")), "a\\");
+
+        let content = parse_inlines("ab   \ncd");
+        match &content[1] {
+            PhrasingContent::StaticPhrasingContent(StaticPhrasingContent::Break(b)) => {
+                let position = b.position.unwrap();
+                assert_eq!(
+                    (position.start.offset, position.end.offset),
+                    (Some(2), Some(6))
+                );
+            }
+            other => panic!("expected break, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn raw_html() {
+        assert_eq!(
+            outline(&parse_inlines(
+                "a <b class=\"c\">*d*</b> <!-- e\nf --> <?g?>"
+            )),
+            "a html(<b class=\"c\">)em(d)html(</b>) html(<!-- e\nf -->) html(<?g?>)"
+        );
+        assert_eq!(
+            outline(&parse_inlines("<a><bab><c2c> <!DOCTYPE x> <![CDATA[<y>]]>")),
+            "html(<a>)html(<bab>)html(<c2c>) html(<!DOCTYPE x>) html(<![CDATA[<y>]]>)"
+        );
+        // HTML takes precedence over emphasis and links that would end inside it
+        assert_eq!(
+            outline(&parse_inlines("*a <b c=\"*\">")),
+            "*a html(<b c=\"*\">)"
+        );
+        assert_eq!(
+            outline(&parse_inlines("<33> <a h*#ref=\"hi\"> <!-- a -- b -->")),
+            "<33> <a h*#ref=\"hi\"> <!-- a -- b -->"
+        );
+    }
+
+    #[test]
+    fn images() {
+        assert_eq!(
+            outline(&parse_inlines("![foo *bar*](train.jpg 'train & tracks')")),
+            "image(train.jpg, Some(\"train & tracks\"), foo bar)"
+        );
+        assert_eq!(
+            outline(&parse_inlines("[![moon](moon.jpg)](/uri)")),
+            "link(/uri, None, image(moon.jpg, None, moon))"
+        );
+        assert_eq!(outline(&parse_inlines("!not an image")), "!not an image");
+    }
+
+    #[test]
+    fn footnotes() {
+        let definitions = Definitions {
+            footnotes: vec!["note".to_string()].into_iter().collect(),
+            ..Definitions::default()
+        };
+        assert_eq!(
+            outline(&parse_with_definitions(
+                "a[^Note] b^[*c* [d](/e)]",
+                &definitions
+            )),
+            "afootnoteReference(note) bfootnote(em(c) link(/e, None, d))"
+        );
+        // labels without a definition stay text
+        assert_eq!(
+            outline(&parse_with_definitions("[^gone] [^x](/y)", &definitions)),
+            "[^gone] link(/y, None, ^x)"
+        );
+        assert_eq!(
+            outline(&parse_inlines("[^a b] [^] ^[x *y]")),
+            "[^a b] [^] footnote(x *y)"
+        );
+        assert_eq!(outline(&parse_inlines("x^2 ^[open")), "x^2 ^[open");
+    }
+
+    #[test]
+    fn references() {
+        let definitions = Definitions {
+            links: vec![
+                "foo".to_string(),
+                "bar baz".to_string(),
+                "*foo*".to_string(),
+            ]
+            .into_iter()
+            .collect(),
+            ..Definitions::default()
+        };
+        let parse = |i| outline(&parse_with_definitions(i, &definitions));
+
+        assert_eq!(parse("[text][Foo]"), "linkReference(Full, foo, text)");
+        assert_eq!(
+            parse("[Bar\n  BAZ][]"),
+            "linkReference(Collapsed, bar baz, Bar\n  BAZ)"
+        );
+        assert_eq!(
+            parse("[*foo*] bar"),
+            "linkReference(Shortcut, *foo*, em(foo)) bar"
+        );
+        assert_eq!(parse("![foo]"), "imageReference(Shortcut, foo, foo)");
+        // undefined labels are not references
+        assert_eq!(parse("[text][nope] [nope]"), "[text][nope] [nope]");
+        // inline links take precedence
+        assert_eq!(parse("[foo](/uri)"), "link(/uri, None, foo)");
+        // the link text of shortcut references may not contain brackets
+        assert_eq!(parse("[[foo]]"), "[linkReference(Shortcut, foo, foo)]");
+    }
+
+    #[test]
+    fn positions() {
+        let content = parse_inlines("a **b** [c](/d) `e`*");
+        let spans: Vec<(usize, usize)> = content
+            .iter()
+            .map(|child| {
+                let position = match child {
+                    PhrasingContent::Link(link) => link.position,
+                    PhrasingContent::LinkReference(reference) => reference.position,
+                    PhrasingContent::StaticPhrasingContent(content) => match content {
+                        StaticPhrasingContent::Text(text) => text.position,
+                        StaticPhrasingContent::Strong(strong) => strong.position,
+                        StaticPhrasingContent::InlineCode(code) => code.position,
+                        other => panic!("unexpected {:?}", other),
+                    },
+                };
+                let position = position.unwrap();
+                (
+                    position.start.offset.unwrap() as usize,
+                    position.end.offset.unwrap() as usize,
+                )
+            })
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                (0, 2),
+                (2, 7),
+                (7, 8),
+                (8, 15),
+                (15, 16),
+                (16, 19),
+                (19, 20)
+            ]
+        );
+    }
+
+    #[test]
+    fn unmatched_markup_is_text() {
+        let content = parse_inlines("a * b\nc");
+        assert_eq!(content.len(), 1);
+        match &content[0] {
+            PhrasingContent::StaticPhrasingContent(StaticPhrasingContent::Text(text)) => {
+                assert_eq!(text.value.as_deref(), Some(&b"a * b\nc"[..]))
+            }
+            other => panic!("expected text, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,338 @@
+use nom::{
+    branch::alt,
+    character::complete::{char, line_ending, space0},
+    combinator::{eof, map, opt, recognize},
+    error::{Error, ErrorKind},
+    sequence::{pair, preceded, tuple},
+    IResult,
+};
+
+// Link labels, destinations and titles are shared by link reference definitions and inline links.
+// https://spec.commonmark.org/0.30/#links
+
+fn error(i: &str, kind: ErrorKind) -> nom::Err<Error<&str>> {
+    nom::Err::Error(Error::new(i, kind))
+}
+
+fn is_ascii_punctuation(c: char) -> bool {
+    c.is_ascii_punctuation()
+}
+
+// optional spaces or tabs, including up to one line ending
+pub(crate) fn spnl(i: &str) -> IResult<&str, &str> {
+    recognize(tuple((space0, opt(line_ending), space0)))(i)
+}
+
+// A link label begins with a left bracket ([) and ends with the first right bracket (]) that is not backslash-escaped.
+// Between these brackets there must be at least one character that is not a space, tab, or line ending.
+// Unescaped square bracket characters are not allowed inside the opening and closing square brackets of link labels.
+// A link label can have at most 999 characters inside the square brackets.
+// Returns the raw label between the brackets.
+pub(crate) fn link_label(i: &str) -> IResult<&str, &str> {
+    let inner = match i.strip_prefix('[') {
+        Some(inner) => inner,
+        None => return Err(error(i, ErrorKind::Char)),
+    };
+
+    let mut chars = inner.char_indices();
+    // the number of characters before the current one
+    let mut count = 0;
+    while let Some((idx, c)) = chars.next() {
+        if count > 999 {
+            break;
+        }
+        count += 1;
+        match c {
+            '\\' => {
+                if let Some((_, next)) = chars.clone().next() {
+                    if is_ascii_punctuation(next) {
+                        chars.next();
+                        count += 1;
+                    }
+                }
+            }
+            '[' => break,
+            ']' => {
+                let label = &inner[..idx];
+                if label.trim_matches([' ', '\t', '\n', '\r']).is_empty() {
+                    break;
+                }
+                return Ok((&inner[idx + 1..], label));
+            }
+            _ => {}
+        }
+    }
+
+    Err(error(i, ErrorKind::TakeUntil))
+}
+
+// A footnote label is a link label that starts with a caret and contains no spaces, tabs or line endings.
+// Returns the label without the caret.
+pub(crate) fn footnote_label(i: &str) -> IResult<&str, &str> {
+    let (rest, label) = link_label(i)?;
+    match label.strip_prefix('^') {
+        Some(label) if !label.is_empty() && !label.contains(char::is_whitespace) => {
+            Ok((rest, label))
+        }
+        _ => Err(error(i, ErrorKind::Verify)),
+    }
+}
+
+// A link destination consists of either
+// - a sequence of zero or more characters between an opening < and a closing > that contains no line endings
+//   or unescaped < or > characters, or
+// - a nonempty sequence of characters that does not start with <, does not include ASCII control characters
+//   or space character, and includes parentheses only if (a) they are backslash-escaped or (b) they are part
+//   of a balanced pair of unescaped parentheses.
+// Returns the raw destination, without angle brackets.
+pub(crate) fn link_destination(i: &str) -> IResult<&str, &str> {
+    if let Some(inner) = i.strip_prefix('<') {
+        let mut chars = inner.char_indices();
+        while let Some((idx, c)) = chars.next() {
+            match c {
+                '\\' => {
+                    if let Some((_, next)) = chars.clone().next() {
+                        if is_ascii_punctuation(next) {
+                            chars.next();
+                        }
+                    }
+                }
+                '>' => return Ok((&inner[idx + 1..], &inner[..idx])),
+                '<' | '\n' | '\r' => break,
+                _ => {}
+            }
+        }
+        return Err(error(i, ErrorKind::Char));
+    }
+
+    let mut open_parens = 0;
+    let mut end = i.len();
+    let mut chars = i.char_indices();
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some((_, next)) = chars.clone().next() {
+                    if is_ascii_punctuation(next) {
+                        chars.next();
+                    }
+                }
+            }
+            '(' => open_parens += 1,
+            ')' => {
+                if open_parens == 0 {
+                    end = idx;
+                    break;
+                }
+                open_parens -= 1;
+            }
+            c if c == ' ' || c.is_ascii_control() => {
+                end = idx;
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    if (end == 0 && !i.starts_with(')')) || open_parens != 0 {
+        return Err(error(i, ErrorKind::TakeWhile1));
+    }
+
+    Ok((&i[end..], &i[..end]))
+}
+
+// A link title consists of either
+// - a sequence of zero or more characters between straight double-quote characters ("),
+//   including a " character only if it is backslash-escaped, or
+// - a sequence of zero or more characters between straight single-quote characters ('),
+//   including a ' character only if it is backslash-escaped, or
+// - a sequence of zero or more characters between matching parentheses ((...)),
+//   including a ( or ) character only if it is backslash-escaped.
+// Returns the raw title without its delimiters.
+pub(crate) fn link_title(i: &str) -> IResult<&str, &str> {
+    let (open, close) = match i.chars().next() {
+        Some('"') => ('"', '"'),
+        Some('\'') => ('\'', '\''),
+        Some('(') => ('(', ')'),
+        _ => return Err(error(i, ErrorKind::Char)),
+    };
+
+    let inner = &i[1..];
+    let mut chars = inner.char_indices();
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some((_, next)) = chars.clone().next() {
+                    if is_ascii_punctuation(next) {
+                        chars.next();
+                    }
+                }
+            }
+            c if c == close => return Ok((&inner[idx + 1..], &inner[..idx])),
+            c if c == open && open == '(' => break,
+            _ => {}
+        }
+    }
+
+    Err(error(i, ErrorKind::Char))
+}
+
+// like spnl but at least one space, tab or line ending must be present
+fn spnl1(i: &str) -> IResult<&str, &str> {
+    match spnl(i) {
+        Ok((rest, ws)) if !ws.is_empty() => Ok((rest, ws)),
+        _ => Err(error(i, ErrorKind::Space)),
+    }
+}
+
+// An inline link consists of a link text followed immediately by a left parenthesis (, an optional link destination,
+// an optional link title, and a right parenthesis ). These four components may be separated by spaces, tabs,
+// and up to one line ending. If both link destination and link title are present, they must be separated by
+// spaces, tabs, and up to one line ending.
+// Parses everything after the link text, returning the raw destination and title.
+pub(crate) fn inline_link(i: &str) -> IResult<&str, (&str, Option<&str>)> {
+    let (i, _) = pair(char('('), spnl)(i)?;
+    let (i, destination) = opt(link_destination)(i)?;
+    let (i, title) = opt(preceded(spnl1, link_title))(i)?;
+    let (i, _) = pair(spnl, char(')'))(i)?;
+    Ok((i, (destination.unwrap_or_default(), title)))
+}
+
+fn end_of_line(i: &str) -> IResult<&str, &str> {
+    recognize(pair(space0, alt((line_ending, eof))))(i)
+}
+
+/// A link reference definition as found in the source, before escapes and entities are processed.
+#[derive(Debug, PartialEq)]
+pub(crate) struct RawDefinition<'a> {
+    pub(crate) label: &'a str,
+    pub(crate) destination: &'a str,
+    pub(crate) title: Option<&'a str>,
+}
+
+// A link reference definition consists of a link label, followed by a colon (:), optional spaces or tabs
+// (including up to one line ending), a link destination, optional spaces or tabs (including up to one line ending),
+// and an optional link title, which if it is present must be separated from the link destination by spaces or tabs.
+// No further character may occur.
+pub(crate) fn link_reference_definition(i: &str) -> IResult<&str, RawDefinition<'_>> {
+    let (rest, (label, destination)) = pair(
+        link_label,
+        preceded(pair(char(':'), spnl), link_destination),
+    )(i)?;
+
+    // the title must be separated from the destination by whitespace and be followed by the end of the line,
+    // otherwise the definition is still valid without the title as long as the destination ends the line
+    let with_title = map(tuple((spnl1, link_title, end_of_line)), |(_, title, _)| {
+        title
+    })(rest);
+
+    match with_title {
+        Ok((rest, title)) => Ok((
+            rest,
+            RawDefinition {
+                label,
+                destination,
+                title: Some(title),
+            },
+        )),
+        Err(_) => {
+            let (rest, _) = end_of_line(rest)?;
+            Ok((
+                rest,
+                RawDefinition {
+                    label,
+                    destination,
+                    title: None,
+                },
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels() {
+        assert_eq!(link_label("[foo] bar"), Ok((" bar", "foo")));
+        assert_eq!(link_label("[fo\\]o]"), Ok(("", "fo\\]o")));
+        assert!(link_label("[  ]").is_err());
+        assert!(link_label("[fo[o]").is_err());
+        // the limit is in characters
+        let label = "é".repeat(999);
+        assert_eq!(
+            link_label(&format!("[{}]", label)),
+            Ok(("", label.as_str()))
+        );
+        assert!(link_label(&format!("[{}]", "a".repeat(1000))).is_err());
+        let label = format!("{}\\]", "a".repeat(997));
+        assert_eq!(
+            link_label(&format!("[{}]", label)),
+            Ok(("", label.as_str()))
+        );
+        assert_eq!(footnote_label("[^note-1]: a"), Ok((": a", "note-1")));
+        assert!(footnote_label("[^]").is_err());
+        assert!(footnote_label("[^a b]").is_err());
+        assert!(footnote_label("[a]").is_err());
+    }
+
+    #[test]
+    fn destinations() {
+        assert_eq!(link_destination("<my url> x"), Ok((" x", "my url")));
+        assert_eq!(link_destination("/url(a(b)) x"), Ok((" x", "/url(a(b))")));
+        assert_eq!(link_destination("/url) x"), Ok((") x", "/url")));
+        assert!(link_destination("/url(a").is_err());
+        assert!(link_destination("<a<b>").is_err());
+    }
+
+    #[test]
+    fn titles() {
+        assert_eq!(link_title("\"a \\\" b\" x"), Ok((" x", "a \\\" b")));
+        assert_eq!(link_title("(a b)"), Ok(("", "a b")));
+        assert!(link_title("(a (b)").is_err());
+    }
+
+    #[test]
+    fn inline_links() {
+        assert_eq!(
+            inline_link("(/uri \"title\") x"),
+            Ok((" x", ("/uri", Some("title"))))
+        );
+        assert_eq!(inline_link("()"), Ok(("", ("", None))));
+        assert_eq!(inline_link("(<my uri>)"), Ok(("", ("my uri", None))));
+        assert_eq!(
+            inline_link("(\n  /uri\n  'title'\n)"),
+            Ok(("", ("/uri", Some("title"))))
+        );
+        assert!(inline_link("(/my uri)").is_err());
+        assert!(inline_link("(/uri \"title\" x)").is_err());
+    }
+
+    #[test]
+    fn definitions() {
+        assert_eq!(
+            link_reference_definition("[foo]: /url \"title\"\nrest"),
+            Ok((
+                "rest",
+                RawDefinition {
+                    label: "foo",
+                    destination: "/url",
+                    title: Some("title")
+                }
+            ))
+        );
+        assert_eq!(
+            link_reference_definition("[foo]:\n/url\n\"title\" ok"),
+            Ok((
+                "\"title\" ok",
+                RawDefinition {
+                    label: "foo",
+                    destination: "/url",
+                    title: None
+                }
+            ))
+        );
+        assert!(link_reference_definition("[foo]: /url \"title\" ok").is_err());
+        assert!(link_reference_definition("[foo]:").is_err());
+    }
+}
@@ -0,0 +1,389 @@
+// use crate::nom::markdown::MarkdownInline;
+// use crate::nom::markdown::MarkdownText;
+use crate::ast::{FlowContentFrontmatter, FrontmatterContent, Json, Root, Toml, YAML};
+use crate::front_matter;
+use crate::markdown::{Options, ParseError};
+use block::BlockParser;
+use nom::{character::complete::line_ending, combinator::opt, IResult};
+use position::LineIndex;
+
+pub(crate) use entity::unescape_with_offsets;
+pub(crate) use link::footnote_label;
+
+mod autolink;
+mod block;
+mod entities;
+mod entity;
+mod html;
+mod inline;
+mod link;
+mod position;
+
+// The fences tell the format: `---` for YAML, `+++` for TOML and braces for JSON.
+// The front matter spans from the start of the input to the end of its closing fence.
+fn parse_front_matter<'a>(i: &'a str, lines: &LineIndex) -> IResult<&'a str, FrontmatterContent> {
+    let position = |rest: &str| Some(lines.position(0, i.len() - rest.len()));
+    if let Ok((rest, value)) = front_matter::yaml(i) {
+        let value = value.as_bytes().to_vec();
+        let position = position(rest);
+        return Ok((rest, FrontmatterContent::YAML(YAML { value, position })));
+    }
+    if let Ok((rest, value)) = front_matter::toml(i) {
+        let value = value.as_bytes().to_vec();
+        let position = position(rest);
+        return Ok((rest, FrontmatterContent::Toml(Toml { value, position })));
+    }
+    let (rest, value) = front_matter::json(i)?;
+    let value = value.as_bytes().to_vec();
+    let position = position(rest);
+    Ok((rest, FrontmatterContent::Json(Json { value, position })))
+}
+
+// from nom json example
+// fn key_value<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+//     i: &'a str,
+//   ) -> IResult<&'a str, (&'a str, JsonValue), E> {
+//     separated_pair(
+//       preceded(sp, string),
+//       cut(preceded(sp, char(':'))),
+//       json_value,
+//     )(i)
+//   }
+
+// // A line consisting of 0-3 spaces of indentation, followed by a sequence of three or more matching -, _, or * characters,
+// // each followed optionally by any number of spaces or tabs, forms a thematic break.
+// fn parse_thematic_break(i: &str) -> IResult<&str, &str> {
+//     terminated(
+//         many_m_n(0, 3, tag(" ")),
+//         many_till(alt((tag("-"), tag("_"), tag("*")), tag("\n")))
+//     )(i)
+// }
+
+fn parse_root<'a>(i: &'a str, options: &Options) -> IResult<&'a str, Root> {
+    let lines = LineIndex::new(i);
+    let mut children = Vec::new();
+    let mut body = i;
+    if options.front_matter {
+        if let Ok((rest, front_matter)) = parse_front_matter(i, &lines) {
+            children.push(FlowContentFrontmatter::FrontmatterContent(front_matter));
+            body = opt(line_ending)(rest)?.0;
+        }
+    }
+    children.extend(
+        BlockParser::new(&lines, options)
+            .parse(i, i.len() - body.len())
+            .into_iter()
+            .map(FlowContentFrontmatter::FlowContent),
+    );
+    let mut root = Root::new(children);
+    root.position = Some(lines.position(0, i.len()));
+    Ok(("", root))
+}
+
+/// Parses a markdown document into an mdast [`Root`] using the default [`Options`].
+pub fn parse(input: &str) -> Result<Root, ParseError> {
+    parse_with_options(input, &Options::default())
+}
+
+/// Parses a markdown document into an mdast [`Root`].
+///
+/// The whole input must be consumed, otherwise a [`ParseError`] pointing at the
+/// first byte that could not be parsed is returned.
+pub fn parse_with_options(input: &str, options: &Options) -> Result<Root, ParseError> {
+    match parse_root(input, options) {
+        Ok(("", root)) => Ok(root),
+        Ok((remaining, _)) => Err(ParseError::new(
+            "unexpected input",
+            input.len() - remaining.len(),
+        )),
+        Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => Err(ParseError::new(
+            format!("{:?}", e.code),
+            input.len() - e.input.len(),
+        )),
+        Err(nom::Err::Incomplete(_)) => Err(ParseError::new("incomplete input", input.len())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::{
+        Content, FlowContent, Heading, List, PhrasingContent, Position, StaticPhrasingContent,
+    };
+
+    #[test]
+    fn test_parse_front_matter() {
+        let string = "---\nAuthor: Sean\n---";
+        match parse_front_matter(string, &LineIndex::new(string)) {
+            Ok(("", FrontmatterContent::YAML(yaml))) => assert_eq!(yaml.value, b"Author: Sean"),
+            other => panic!("expected yaml front matter, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn front_matter_formats() {
+        let root = parse("+++\ntitle = \"Hello\"\n+++\n# Header\n").unwrap();
+        match root.front_matter() {
+            Some(FrontmatterContent::Toml(toml)) => {
+                assert_eq!(toml.value, b"title = \"Hello\"");
+                assert_eq!(toml.position.unwrap().end.line, 3);
+            }
+            other => panic!("expected toml front matter, got {:?}", other),
+        }
+        assert_eq!(root.flow_content().count(), 1);
+
+        let root = parse("{\n  \"title\": \"Hello\"\n}\n\n# Header\n").unwrap();
+        match root.front_matter() {
+            Some(FrontmatterContent::Json(json)) => {
+                assert_eq!(json.value, b"{\n  \"title\": \"Hello\"\n}");
+                assert_eq!(json.position.unwrap().end.line, 3);
+            }
+            other => panic!("expected json front matter, got {:?}", other),
+        }
+        assert_eq!(root.flow_content().count(), 1);
+
+        let root = parse("{{< figure >}}\n").unwrap();
+        assert!(root.front_matter().is_none());
+    }
+
+    #[test]
+    fn front_matter_is_root_head() {
+        let root = parse("---\nAuthor: Sean\n---\n# Header\n").unwrap();
+        assert_eq!(root.children.len(), 2);
+        match root.front_matter() {
+            Some(FrontmatterContent::YAML(yaml)) => assert_eq!(yaml.value, b"Author: Sean"),
+            other => panic!("expected yaml front matter, got {:?}", other),
+        }
+        assert_eq!(root.flow_content().count(), 1);
+    }
+
+    #[test]
+    fn front_matter_disabled() {
+        let root = parse_with_options("---\nAuthor: Sean\n---\n", &Options::commonmark()).unwrap();
+        assert!(root.front_matter().is_none());
+        // a thematic break followed by a setext heading
+        assert_eq!(root.flow_content().count(), 2);
+    }
+
+    #[test]
+    fn paragraphs() {
+        let root = parse("# Header\nnot a header\nbut a paragraph\n\nanother").unwrap();
+        let flow_content: Vec<&FlowContent> = root.flow_content().collect();
+        assert_eq!(flow_content.len(), 3);
+        assert!(matches!(
+            flow_content[1],
+            FlowContent::Content(Content::Paragraph(_))
+        ));
+        assert!(matches!(
+            flow_content[2],
+            FlowContent::Content(Content::Paragraph(_))
+        ));
+    }
+
+    #[test]
+    fn document() {
+        let root = parse(include_str!("../../../tests/fixtures/document.md")).unwrap();
+        let flow_content: Vec<&FlowContent> = root.flow_content().collect();
+        assert_eq!(flow_content.len(), 5);
+        assert!(matches!(
+            flow_content[0],
+            FlowContent::Heading(Heading { depth: 1, .. })
+        ));
+        match flow_content[1] {
+            FlowContent::Content(Content::Paragraph(paragraph)) => {
+                // "***bold italic***" nests strong inside emphasis
+                match paragraph.children.last() {
+                    Some(PhrasingContent::StaticPhrasingContent(
+                        StaticPhrasingContent::Emphasis(emphasis),
+                    )) => assert!(matches!(
+                        emphasis.children[..],
+                        [PhrasingContent::StaticPhrasingContent(
+                            StaticPhrasingContent::Strong(_)
+                        )]
+                    )),
+                    other => panic!("expected emphasis, got {:?}", other),
+                }
+            }
+            other => panic!("expected paragraph, got {:?}", other),
+        }
+        assert!(matches!(
+            flow_content[2],
+            FlowContent::Heading(Heading { depth: 2, .. })
+        ));
+        match flow_content[3] {
+            FlowContent::Code(code) => {
+                assert_eq!(code.lang.as_deref(), Some("json"));
+                assert_eq!(code.value, b"{\n  \"key\": \"value\"\n}");
+            }
+            other => panic!("expected code, got {:?}", other),
+        }
+        match flow_content[4] {
+            FlowContent::BlockQuote(quote) => assert_eq!(quote.children.len(), 1),
+            other => panic!("expected blockquote, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn positions() {
+        let root = parse(include_str!("../../../tests/fixtures/document.md")).unwrap();
+        let position = |position: &Option<Position>| {
+            let position = position.unwrap();
+            (
+                position.start.line,
+                position.start.column,
+                position.end.line,
+                position.end.column,
+            )
+        };
+        assert_eq!(position(&root.position), (1, 1, 14, 7));
+        let flow_content: Vec<&FlowContent> = root.flow_content().collect();
+        match flow_content[1] {
+            FlowContent::Content(Content::Paragraph(paragraph)) => {
+                assert_eq!(position(&paragraph.position), (3, 1, 3, 84));
+                match &paragraph.children[1] {
+                    PhrasingContent::StaticPhrasingContent(StaticPhrasingContent::Emphasis(
+                        emphasis,
+                    )) => assert_eq!(position(&emphasis.position), (3, 18, 3, 26)),
+                    other => panic!("expected emphasis, got {:?}", other),
+                }
+            }
+            other => panic!("expected paragraph, got {:?}", other),
+        }
+        match flow_content[3] {
+            FlowContent::Code(code) => assert_eq!(position(&code.position), (7, 1, 11, 4)),
+            other => panic!("expected code, got {:?}", other),
+        }
+        match flow_content[4] {
+            FlowContent::BlockQuote(quote) => {
+                assert_eq!(position(&quote.position), (13, 1, 14, 7));
+                // the lazy continuation line belongs to the paragraph in the blockquote
+                match &quote.children[0] {
+                    FlowContent::Content(Content::Paragraph(paragraph)) => {
+                        assert_eq!(position(&paragraph.position), (13, 3, 14, 7))
+                    }
+                    other => panic!("expected paragraph, got {:?}", other),
+                }
+            }
+            other => panic!("expected blockquote, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn front_matter_position() {
+        let root = parse("---\nAuthor: Sean\n---\n# Header\n").unwrap();
+        match root.front_matter() {
+            Some(FrontmatterContent::YAML(yaml)) => {
+                let position = yaml.position.unwrap();
+                assert_eq!((position.start.line, position.start.column), (1, 1));
+                assert_eq!((position.end.line, position.end.column), (3, 4));
+            }
+            other => panic!("expected yaml front matter, got {:?}", other),
+        }
+        match root.flow_content().next() {
+            Some(FlowContent::Heading(heading)) => {
+                assert_eq!(heading.position.unwrap().start.line, 4)
+            }
+            other => panic!("expected heading, got {:?}", other),
+        };
+    }
+
+    #[test]
+    fn containers() {
+        let root = parse("> - a\n>   - b\n> lazy\n\n1. one\n2) two\n").unwrap();
+        let flow_content: Vec<&FlowContent> = root.flow_content().collect();
+        assert_eq!(flow_content.len(), 3);
+        match flow_content[0] {
+            FlowContent::BlockQuote(quote) => match &quote.children[..] {
+                [FlowContent::List(list)] => {
+                    assert_eq!(list.ordered, Some(false));
+                    assert_eq!(list.children.len(), 1);
+                }
+                other => panic!("expected a list, got {:?}", other),
+            },
+            other => panic!("expected blockquote, got {:?}", other),
+        }
+        // changing the delimiter starts a new list
+        assert!(matches!(
+            flow_content[1],
+            FlowContent::List(List { start: Some(1), .. })
+        ));
+        assert!(matches!(
+            flow_content[2],
+            FlowContent::List(List { start: Some(2), .. })
+        ));
+    }
+
+    #[test]
+    fn leaf_blocks() {
+        let root = parse(
+            "[foo]: /url \"title\"\n\n***\nSetext\n===\n\n    indented\n      code\n\n<div>\n*html*\n</div>\n",
+        )
+        .unwrap();
+        let flow_content: Vec<&FlowContent> = root.flow_content().collect();
+        assert_eq!(flow_content.len(), 5);
+        assert!(matches!(
+            flow_content[0],
+            FlowContent::Content(Content::Definition(_))
+        ));
+        assert!(matches!(flow_content[1], FlowContent::ThematicBreak(_)));
+        assert!(matches!(
+            flow_content[2],
+            FlowContent::Heading(Heading {
+                depth: 1,
+                setext: true,
+                ..
+            })
+        ));
+        match flow_content[3] {
+            FlowContent::Code(code) => {
+                assert_eq!(code.lang, None);
+                assert_eq!(code.value, b"indented\n  code");
+            }
+            other => panic!("expected code, got {:?}", other),
+        }
+        match flow_content[4] {
+            FlowContent::HTML(html) => assert_eq!(html.value, b"<div>\n*html*\n</div>"),
+            other => panic!("expected html, got {:?}", other),
+        }
+    }
+
+    // #[test]
+    // fn header() {
+    //     let string = "# Header";
+    //     assert_eq!(
+    //         parse_header(string),
+    //         Ok(("", ""))
+    //     );
+    // }
+
+    #[test]
+    fn header() {
+        let root = parse("# Header\n").unwrap();
+        let flow_content: Vec<&FlowContent> = root.flow_content().collect();
+        let heading = match flow_content[..] {
+            [FlowContent::Heading(heading)] => heading,
+            _ => panic!("expected a heading, got {:?}", flow_content),
+        };
+        assert_eq!((heading.depth, heading.setext), (1, false));
+        match &heading.children[..] {
+            [PhrasingContent::StaticPhrasingContent(StaticPhrasingContent::Text(text))] => {
+                assert_eq!(text.value.as_deref(), Some(&b"Header"[..]))
+            }
+            other => panic!("expected text, got {:?}", other),
+        }
+        let position = heading.position.unwrap();
+        assert_eq!(
+            (
+                position.start.column,
+                position.end.column,
+                position.end.offset
+            ),
+            (1, 9, Some(8))
+        );
+
+        let serialized = serde_json::to_string(&root).unwrap();
+        assert!(serialized.contains(
+            r#"{"type":"heading","depth":1,"children":[{"type":"text","value":"Header""#
+        ));
+    }
+}
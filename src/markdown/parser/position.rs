@@ -0,0 +1,183 @@
+use crate::ast::{Point, Position};
+
+// Nodes are located by byte offsets into the source which are turned into unist points at the end.
+// Text handed from the block parser to the inline parser is assembled from pieces of lines, so a
+// SourceMap keeps track of where each piece came from.
+
+/// Start offsets of every line in the source, used to turn byte offsets into points.
+/// Points count columns and offsets in UTF-16 code units like remark does.
+#[derive(Debug)]
+pub(crate) struct LineIndex<'a> {
+    input: &'a str,
+    // (byte offset, UTF-16 offset) of the start of each line
+    starts: Vec<(usize, usize)>,
+}
+
+impl LineIndex<'_> {
+    pub(crate) fn new(input: &str) -> LineIndex<'_> {
+        let bytes = input.as_bytes();
+        let mut starts = vec![0];
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\n' => starts.push(i + 1),
+                b'\r' => {
+                    if bytes.get(i + 1) == Some(&b'\n') {
+                        i += 1;
+                    }
+                    starts.push(i + 1);
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        let mut units = 0;
+        let starts = starts
+            .iter()
+            .enumerate()
+            .map(|(line, &start)| {
+                if line > 0 {
+                    units += utf16_len(&input[starts[line - 1]..start]);
+                }
+                (start, units)
+            })
+            .collect();
+        LineIndex { input, starts }
+    }
+
+    /// The point of a byte offset.
+    pub(crate) fn point(&self, offset: usize) -> Point {
+        let line = match self
+            .starts
+            .binary_search_by(|(start, _)| start.cmp(&offset))
+        {
+            Ok(line) => line,
+            Err(next) => next - 1,
+        };
+        let (start, units) = self.starts[line];
+        let column = utf16_len(&self.input[start..offset]);
+        Point::new(
+            line as u64 + 1,
+            column as u64 + 1,
+            Some((units + column) as u64),
+        )
+    }
+
+    pub(crate) fn position(&self, start: usize, end: usize) -> Position {
+        Position::new(self.point(start), self.point(end), None)
+    }
+}
+
+fn utf16_len(s: &str) -> usize {
+    s.chars().map(char::len_utf16).sum()
+}
+
+/// Maps offsets into text assembled from pieces of the source back to source offsets.
+/// Each segment starts a piece that continues byte for byte in the source.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct SourceMap {
+    // (offset in the assembled text, offset in the source)
+    segments: Vec<(usize, usize)>,
+}
+
+impl SourceMap {
+    pub(crate) fn new(offset: usize, source: usize) -> SourceMap {
+        SourceMap {
+            segments: vec![(offset, source)],
+        }
+    }
+
+    /// Records that the text from `offset` on was taken from `source`.
+    pub(crate) fn push(&mut self, offset: usize, source: usize) {
+        if let Some(last) = self.segments.last_mut() {
+            if last.0 == offset {
+                *last = (offset, source);
+                return;
+            }
+        }
+        self.segments.push((offset, source));
+    }
+
+    /// The map of the text after dropping its first `count` bytes.
+    pub(crate) fn skip(&self, count: usize) -> SourceMap {
+        let mut map = SourceMap::new(0, self.start(count));
+        for &(offset, source) in &self.segments {
+            if offset > count {
+                map.push(offset - count, source);
+            }
+        }
+        map
+    }
+
+    /// The source offset of the character at `offset`.
+    pub(crate) fn start(&self, offset: usize) -> usize {
+        let index = match self.segments.binary_search_by(|(o, _)| o.cmp(&offset)) {
+            Ok(index) => index,
+            Err(0) => return self.segments.first().map_or(offset, |(_, s)| *s),
+            Err(next) => next - 1,
+        };
+        let (o, s) = self.segments[index];
+        s + (offset - o)
+    }
+
+    /// The source offset right after the character before `offset`, for use as the end of a range.
+    pub(crate) fn end(&self, offset: usize) -> usize {
+        match offset.checked_sub(1) {
+            Some(last) => self.start(last) + 1,
+            None => self.start(0),
+        }
+    }
+}
+
+/// Locates ranges of assembled text in the source.
+pub(crate) struct Locator<'a> {
+    pub(crate) lines: &'a LineIndex<'a>,
+    pub(crate) map: &'a SourceMap,
+}
+
+impl Locator<'_> {
+    pub(crate) fn position(&self, start: usize, end: usize) -> Option<Position> {
+        Some(self.lines.position(
+            self.map.start(start),
+            self.map.end(end).max(self.map.start(start)),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn points() {
+        let lines = LineIndex::new("ab\ncd\r\n\ref");
+        assert_eq!(lines.point(0), Point::new(1, 1, Some(0)));
+        assert_eq!(lines.point(2), Point::new(1, 3, Some(2)));
+        assert_eq!(lines.point(4), Point::new(2, 2, Some(4)));
+        assert_eq!(lines.point(7), Point::new(3, 1, Some(7)));
+        assert_eq!(lines.point(9), Point::new(4, 2, Some(9)));
+
+        // é is two bytes and one UTF-16 unit, 🎉 is four bytes and two units
+        let lines = LineIndex::new("é 🎉x\né");
+        assert_eq!(lines.point(3), Point::new(1, 3, Some(2)));
+        assert_eq!(lines.point(7), Point::new(1, 5, Some(4)));
+        assert_eq!(lines.point(9), Point::new(2, 1, Some(6)));
+        assert_eq!(lines.point(11), Point::new(2, 2, Some(7)));
+    }
+
+    #[test]
+    fn source_map() {
+        // "foo\nbar" assembled from "  foo" on line 1 and "    bar" on line 2
+        let mut map = SourceMap::new(0, 2);
+        map.push(4, 10);
+        assert_eq!(map.start(0), 2);
+        assert_eq!(map.start(3), 5);
+        assert_eq!(map.end(3), 5);
+        assert_eq!(map.start(4), 10);
+        assert_eq!(map.end(7), 13);
+
+        let skipped = map.skip(2);
+        assert_eq!(skipped.start(0), 4);
+        assert_eq!(skipped.start(2), 10);
+    }
+}
@@ -0,0 +1,305 @@
+use marcup::ast::{
+    AlignType, Content, FlowContent, ListContent, PhrasingContent, Root, RowContent,
+    StaticPhrasingContent, TableRow,
+};
+
+// Renders an mdast tree to HTML following the conventions of the CommonMark reference implementation
+// so parser output can be compared against the examples in the spec.
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// percent-encodes a url the way the reference implementation does, leaving existing escapes alone
+fn encode_url(url: &str) -> String {
+    let mut encoded = String::with_capacity(url.len());
+    let bytes = url.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        if b == b'%'
+            && i + 2 < bytes.len()
+            && bytes[i + 1].is_ascii_hexdigit()
+            && bytes[i + 2].is_ascii_hexdigit()
+        {
+            encoded.push_str(&url[i..i + 3]);
+            i += 3;
+            continue;
+        }
+        if b.is_ascii_alphanumeric() || b";/?:@&=+$,-_.!~*'()#".contains(&b) {
+            encoded.push(b as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", b));
+        }
+        i += 1;
+    }
+    encoded
+}
+
+fn utf8(value: &[u8]) -> &str {
+    std::str::from_utf8(value).unwrap_or_default()
+}
+
+struct Renderer<'a> {
+    root: &'a Root,
+    out: String,
+}
+
+impl Renderer<'_> {
+    // ensures the output ends with a newline
+    fn cr(&mut self) {
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+    }
+
+    fn flow(&mut self, content: &FlowContent, tight: bool) {
+        match content {
+            FlowContent::BlockQuote(quote) => {
+                self.cr();
+                self.out.push_str("<blockquote>");
+                self.cr();
+                for child in &quote.children {
+                    self.flow(child, false);
+                }
+                self.cr();
+                self.out.push_str("</blockquote>");
+                self.cr();
+            }
+            FlowContent::Code(code) => {
+                self.cr();
+                self.out.push_str("<pre><code");
+                if let Some(lang) = &code.lang {
+                    self.out
+                        .push_str(&format!(" class=\"language-{}\"", escape(lang)));
+                }
+                self.out.push('>');
+                let value = utf8(&code.value);
+                self.out.push_str(&escape(value));
+                if !value.is_empty() {
+                    self.out.push('\n');
+                }
+                self.out.push_str("</code></pre>");
+                self.cr();
+            }
+            FlowContent::Content(Content::Paragraph(paragraph)) => {
+                if tight {
+                    self.phrasing(&paragraph.children);
+                } else {
+                    self.cr();
+                    self.out.push_str("<p>");
+                    self.phrasing(&paragraph.children);
+                    self.out.push_str("</p>");
+                    self.cr();
+                }
+            }
+            FlowContent::Content(Content::Definition(_)) => {}
+            // footnotes are not covered by the spec examples so they are left out
+            FlowContent::FootnoteDefinition(_) => {}
+            FlowContent::Heading(heading) => {
+                self.cr();
+                self.out.push_str(&format!("<h{}>", heading.depth));
+                self.phrasing(&heading.children);
+                self.out.push_str(&format!("</h{}>", heading.depth));
+                self.cr();
+            }
+            FlowContent::HTML(html) => {
+                self.cr();
+                self.out.push_str(utf8(&html.value));
+                self.cr();
+            }
+            FlowContent::List(list) => {
+                let ordered = list.ordered.unwrap_or(false);
+                let tight = !list.spread.unwrap_or(false)
+                    && list
+                        .children
+                        .iter()
+                        .all(|ListContent::ListItem(item)| !item.spread.unwrap_or(false));
+                self.cr();
+                match (ordered, list.start) {
+                    (true, Some(start)) if start != 1 => {
+                        self.out.push_str(&format!("<ol start=\"{}\">", start))
+                    }
+                    (true, _) => self.out.push_str("<ol>"),
+                    (false, _) => self.out.push_str("<ul>"),
+                }
+                self.cr();
+                for ListContent::ListItem(item) in &list.children {
+                    self.out.push_str("<li>");
+                    match item.checked {
+                        Some(true) => self
+                            .out
+                            .push_str("<input checked=\"\" disabled=\"\" type=\"checkbox\"> "),
+                        Some(false) => self
+                            .out
+                            .push_str("<input disabled=\"\" type=\"checkbox\"> "),
+                        None => {}
+                    }
+                    for child in &item.children {
+                        self.flow(child, tight);
+                    }
+                    self.out.push_str("</li>");
+                    self.cr();
+                }
+                self.out.push_str(if ordered { "</ol>" } else { "</ul>" });
+                self.cr();
+            }
+            FlowContent::ThematicBreak(_) => {
+                self.cr();
+                self.out.push_str("<hr />");
+                self.cr();
+            }
+            FlowContent::Table(table) => {
+                let align = table.align.as_deref().unwrap_or_default();
+                self.cr();
+                self.out.push_str("<table>\n<thead>\n");
+                if let Some(head) = table.head() {
+                    self.table_row(head, "th", align);
+                }
+                self.out.push_str("</thead>\n");
+                let mut body = table.body().peekable();
+                if body.peek().is_some() {
+                    self.out.push_str("<tbody>\n");
+                    for row in body {
+                        self.table_row(row, "td", align);
+                    }
+                    self.out.push_str("</tbody>\n");
+                }
+                self.out.push_str("</table>\n");
+            }
+        }
+    }
+
+    fn table_row(&mut self, row: &TableRow, tag: &str, align: &[AlignType]) {
+        self.out.push_str("<tr>\n");
+        for (i, RowContent::TableCell(cell)) in row.children.iter().enumerate() {
+            self.out.push('<');
+            self.out.push_str(tag);
+            match align.get(i) {
+                Some(AlignType::Left) => self.out.push_str(" align=\"left\""),
+                Some(AlignType::Right) => self.out.push_str(" align=\"right\""),
+                Some(AlignType::Center) => self.out.push_str(" align=\"center\""),
+                _ => {}
+            }
+            self.out.push('>');
+            self.phrasing(&cell.children);
+            self.out.push_str(&format!("</{}>\n", tag));
+        }
+        self.out.push_str("</tr>\n");
+    }
+
+    fn link(&mut self, url: &str, title: Option<&str>, children: &[StaticPhrasingContent]) {
+        self.out
+            .push_str(&format!("<a href=\"{}\"", escape(&encode_url(url))));
+        if let Some(title) = title {
+            self.out.push_str(&format!(" title=\"{}\"", escape(title)));
+        }
+        self.out.push('>');
+        for content in children {
+            self.static_phrasing(content);
+        }
+        self.out.push_str("</a>");
+    }
+
+    fn image(&mut self, url: &str, title: Option<&str>, alt: Option<&str>) {
+        self.out.push_str(&format!(
+            "<img src=\"{}\" alt=\"{}\"",
+            escape(&encode_url(url)),
+            escape(alt.unwrap_or_default())
+        ));
+        if let Some(title) = title {
+            self.out.push_str(&format!(" title=\"{}\"", escape(title)));
+        }
+        self.out.push_str(" />");
+    }
+
+    fn phrasing(&mut self, children: &[PhrasingContent]) {
+        for child in children {
+            match child {
+                PhrasingContent::Link(link) => {
+                    self.link(&link.url, link.title.as_deref(), &link.children)
+                }
+                PhrasingContent::LinkReference(reference) => {
+                    let definition = self
+                        .root
+                        .resolve(&reference.association)
+                        .expect("references are only created for defined labels");
+                    self.link(
+                        &definition.url,
+                        definition.title.as_deref(),
+                        &reference.children,
+                    )
+                }
+                PhrasingContent::StaticPhrasingContent(content) => self.static_phrasing(content),
+            }
+        }
+    }
+
+    fn static_phrasing(&mut self, content: &StaticPhrasingContent) {
+        match content {
+            StaticPhrasingContent::Break(_) => self.out.push_str("<br />\n"),
+            StaticPhrasingContent::Emphasis(emphasis) => {
+                self.out.push_str("<em>");
+                self.phrasing(&emphasis.children);
+                self.out.push_str("</em>");
+            }
+            StaticPhrasingContent::HTML(html) => self.out.push_str(utf8(&html.value)),
+            StaticPhrasingContent::Image(image) => self.image(
+                &image.url,
+                image.title.as_deref(),
+                image.alternative.alt.as_deref(),
+            ),
+            StaticPhrasingContent::ImageReference(reference) => {
+                let definition = self
+                    .root
+                    .resolve(&reference.association)
+                    .expect("references are only created for defined labels");
+                self.image(
+                    &definition.url,
+                    definition.title.as_deref(),
+                    reference.alternative.alt.as_deref(),
+                )
+            }
+            StaticPhrasingContent::InlineCode(code) => {
+                self.out.push_str("<code>");
+                self.out.push_str(&escape(utf8(&code.value)));
+                self.out.push_str("</code>");
+            }
+            StaticPhrasingContent::Strong(strong) => {
+                self.out.push_str("<strong>");
+                self.phrasing(&strong.children);
+                self.out.push_str("</strong>");
+            }
+            StaticPhrasingContent::Footnote(_) | StaticPhrasingContent::FootnoteReference(_) => {}
+            StaticPhrasingContent::Delete(delete) => {
+                self.out.push_str("<del>");
+                self.phrasing(&delete.children);
+                self.out.push_str("</del>");
+            }
+            StaticPhrasingContent::Text(text) => self
+                .out
+                .push_str(&escape(utf8(text.value.as_deref().unwrap_or_default()))),
+        }
+    }
+}
+
+pub fn render(root: &Root) -> String {
+    let mut renderer = Renderer {
+        root,
+        out: String::new(),
+    };
+    for content in root.flow_content() {
+        renderer.flow(content, false);
+    }
+    renderer.out
+}
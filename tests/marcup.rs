@@ -1,9 +1,839 @@
+use marcup::ast::{
+    from_mdast_json, node_from_mdast_json, AlignType, Association, Code, Content, FlowContent,
+    List, ListContent, MetaAttributeKind, NodeType, PhrasingContent, ReferenceType, Resource, Root,
+    RowContent, StaticPhrasingContent,
+};
+use marcup::markdown::Options;
+use serde_json::json;
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
     fn it_works() {
         assert_eq!(2 + 2, 4);
     }
+
+    #[test]
+    fn parse_heading() {
+        let root = marcup::markdown::parse("## Hello, *World*!\n").unwrap();
+        assert!(root.front_matter().is_none());
+
+        let flow_content: Vec<&FlowContent> = root.flow_content().collect();
+        assert_eq!(flow_content.len(), 1);
+        match flow_content[0] {
+            FlowContent::Heading(heading) => {
+                assert_eq!(heading.depth, 2);
+                assert_eq!(heading.children.len(), 3);
+                assert!(matches!(
+                    heading.children[1],
+                    PhrasingContent::StaticPhrasingContent(StaticPhrasingContent::Emphasis(_))
+                ));
+            }
+            other => panic!("expected heading, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn positions_count_utf16_units() {
+        let root = marcup::markdown::parse("# 🎉 *é*\n\nnext\n").unwrap();
+        let value = serde_json::to_value(&root).unwrap();
+        // 🎉 is two UTF-16 units and é is one, as remark counts them
+        assert_eq!(
+            value["children"][0]["children"][1]["position"],
+            json!({"start": {"line": 1, "column": 6, "offset": 5}, "end": {"line": 1, "column": 9, "offset": 8}})
+        );
+        assert_eq!(
+            value["children"][1]["position"]["start"],
+            json!({"line": 3, "column": 1, "offset": 10})
+        );
+    }
+
+    #[test]
+    fn parse_link_and_image() {
+        let root =
+            marcup::markdown::parse("[docs](https://example.com \"Docs\") ![logo](/logo.png)\n")
+                .unwrap();
+
+        let flow_content: Vec<&FlowContent> = root.flow_content().collect();
+        let children = match flow_content[0] {
+            FlowContent::Content(Content::Paragraph(paragraph)) => &paragraph.children,
+            other => panic!("expected paragraph, got {:?}", other),
+        };
+        match &children[0] {
+            PhrasingContent::Link(link) => {
+                assert_eq!(link.url(), "https://example.com");
+                assert_eq!(link.title(), Some("Docs".to_string()));
+            }
+            other => panic!("expected link, got {:?}", other),
+        }
+        match &children[2] {
+            PhrasingContent::StaticPhrasingContent(StaticPhrasingContent::Image(image)) => {
+                assert_eq!(image.url(), "/logo.png");
+                assert_eq!(image.title(), None);
+                assert_eq!(image.alternative.alt.as_deref(), Some("logo"));
+            }
+            other => panic!("expected image, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn resolve_link_reference() {
+        let root = marcup::markdown::parse(
+            "See [the docs][Docs  Site].\n\n> [docs site]: /docs \"Documentation\"\n> [docs site]: /ignored\n",
+        )
+        .unwrap();
+
+        let flow_content: Vec<&FlowContent> = root.flow_content().collect();
+        let reference = match flow_content[0] {
+            FlowContent::Content(Content::Paragraph(paragraph)) => match &paragraph.children[1] {
+                PhrasingContent::LinkReference(reference) => reference,
+                other => panic!("expected link reference, got {:?}", other),
+            },
+            other => panic!("expected paragraph, got {:?}", other),
+        };
+        assert_eq!(reference.reference.reference_type, ReferenceType::Full);
+        assert_eq!(reference.association.identifier, "docs site");
+        assert_eq!(reference.association.label.as_deref(), Some("Docs  Site"));
+
+        assert_eq!(root.definitions().len(), 2);
+        let definition = root.resolve(&reference.association).unwrap();
+        assert_eq!(definition.url(), "/docs");
+        assert_eq!(definition.title(), Some("Documentation".to_string()));
+
+        assert!(root.resolve(&Association::new("missing")).is_none());
+    }
+
+    #[test]
+    fn normalize_identifier() {
+        assert_eq!(Association::normalize(" Foo\n\t BAR "), "foo bar");
+        assert_eq!(Association::normalize("ẞ"), Association::normalize("SS"));
+    }
+
+    fn without_positions(value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::Object(object) => {
+                object.remove("position");
+                object.values_mut().for_each(without_positions);
+            }
+            serde_json::Value::Array(array) => array.iter_mut().for_each(without_positions),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn serialize_as_mdast() {
+        let root = marcup::markdown::parse("## Hello, *World*!\n").unwrap();
+        let mut value = serde_json::to_value(&root).unwrap();
+        without_positions(&mut value);
+        assert_eq!(
+            value,
+            json!({
+                "type": "root",
+                "children": [{
+                    "type": "heading",
+                    "depth": 2,
+                    "children": [
+                        {"type": "text", "value": "Hello, "},
+                        {"type": "emphasis", "children": [{"type": "text", "value": "World"}]},
+                        {"type": "text", "value": "!"}
+                    ]
+                }]
+            })
+        );
+
+        let root = marcup::markdown::parse("[a][b] ![b]\n\n***\n\n[b]: /d\n").unwrap();
+        let json = serde_json::to_string(&root).unwrap();
+        assert!(json.contains(r#""type":"linkReference","referenceType":"full""#));
+        assert!(json.contains(r#""type":"imageReference","referenceType":"shortcut""#));
+        assert!(json.contains(r#""type":"thematicBreak""#));
+
+        let label = "é".repeat(600);
+        let root = marcup::markdown::parse(&format!("[{0}]\n\n[{0}]: /e\n", label)).unwrap();
+        let json = serde_json::to_string(&root).unwrap();
+        assert!(json.contains(r#""type":"linkReference","referenceType":"shortcut""#));
+    }
+
+    #[test]
+    fn deserialize_mdast() {
+        let root: Root = serde_json::from_str(
+            r#"{"type": "root", "children": [{"type": "blockquote", "children": [
+                {"type": "paragraph", "children": [
+                    {"type": "strong", "children": [{"type": "text", "value": "a"}]},
+                    {"type": "link", "url": "/b", "title": null, "children": []}
+                ]}
+            ]}]}"#,
+        )
+        .unwrap();
+        let flow_content: Vec<&FlowContent> = root.flow_content().collect();
+        match flow_content[..] {
+            [FlowContent::BlockQuote(quote)] => match &quote.children[..] {
+                [FlowContent::Content(Content::Paragraph(paragraph))] => {
+                    assert!(matches!(
+                        paragraph.children[..],
+                        [
+                            PhrasingContent::StaticPhrasingContent(StaticPhrasingContent::Strong(
+                                _
+                            )),
+                            PhrasingContent::Link(_)
+                        ]
+                    ))
+                }
+                ref other => panic!("expected paragraph, got {:?}", other),
+            },
+            ref other => panic!("expected blockquote, got {:?}", other),
+        }
+
+        // nodes must be allowed where they are found
+        let error = serde_json::from_str::<Root>(
+            r#"{"type": "root", "children": [{"type": "text", "value": "a"}]}"#,
+        )
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("expected flow or frontmatter content, found `text`"));
+        let error = serde_json::from_str::<Root>(
+            r#"{"type": "root", "children": [{"type": "paragraph", "children": [{"type": "link", "url": "/", "children": [{"type": "link", "url": "/", "children": []}]}]}]}"#,
+        )
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("expected static phrasing content, found `link`"));
+    }
+
+    #[test]
+    fn import_remark_json() {
+        let root = from_mdast_json(include_str!("fixtures/document.json")).unwrap();
+        let flow_content: Vec<&FlowContent> = root.flow_content().collect();
+        assert_eq!(flow_content.len(), 5);
+        match flow_content[3] {
+            FlowContent::Code(code) => {
+                assert_eq!(code.lang.as_deref(), Some("json"));
+                assert_eq!(code.meta, None);
+                assert_eq!(code.value, b"{\n  \"key\": \"value\"\n}");
+                let position = code.position.unwrap();
+                assert_eq!((position.start.line, position.end.line), (7, 11));
+            }
+            other => panic!("expected code, got {:?}", other),
+        }
+
+        // ecosystem data and the indent of older remark versions are accepted
+        let root = from_mdast_json(
+            r#"{"type": "root", "data": {"quirksMode": false}, "children": [{"type": "thematicBreak",
+                "position": {"start": {"line": 1, "column": 1}, "end": {"line": 1, "column": 4}, "indent": []}}]}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            root.flow_content().next(),
+            Some(FlowContent::ThematicBreak(_))
+        ));
+    }
+
+    #[test]
+    fn import_remark_subtree() {
+        let node = node_from_mdast_json(
+            r#"{"type": "list", "ordered": false, "start": null, "spread": false, "children": [
+                {"type": "listItem", "spread": false, "checked": null, "children": [
+                    {"type": "paragraph", "children": [{"type": "text", "value": "a"}]}
+                ]}
+            ]}"#,
+        )
+        .unwrap();
+        match node {
+            NodeType::List(list) => assert_eq!(list.children.len(), 1),
+            other => panic!("expected list, got {}", other.name()),
+        }
+
+        let node = node_from_mdast_json(r#"{"type": "text", "value": "a"}"#).unwrap();
+        assert_eq!(node.name(), "text");
+
+        // nodes are still only accepted where mdast allows them
+        let error = node_from_mdast_json(
+            r#"{"type": "paragraph", "children": [{"type": "listItem", "children": []}]}"#,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("expected phrasing content"));
+    }
+
+    #[test]
+    fn import_remark_json_errors() {
+        let error = from_mdast_json(
+            r#"{"type": "root", "children": [
+                {"type": "mdxjsEsm", "value": "import a from 'b'"}
+            ]}"#,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("unknown variant `mdxjsEsm`"));
+
+        let error = from_mdast_json(r#"{"type": "paragraph", "children": []}"#).unwrap_err();
+        assert!(error.to_string().contains("expected `root`"));
+
+        let error = from_mdast_json("{\"type\": \"root\",\n\"children\": [}").unwrap_err();
+        assert!(error.to_string().contains("line 2"));
+    }
+
+    #[test]
+    fn parse_table() {
+        let markdown = "Intro\n| Name | Size |\n|:-----|-----:|\n| `a\\|b` | 1 |\n| c |\n";
+        let root = marcup::markdown::parse(markdown).unwrap();
+        let flow_content: Vec<&FlowContent> = root.flow_content().collect();
+        assert!(matches!(
+            flow_content[0],
+            FlowContent::Content(Content::Paragraph(_))
+        ));
+        let table = match flow_content[1] {
+            FlowContent::Table(table) => table,
+            other => panic!("expected table, got {:?}", other),
+        };
+        assert_eq!(
+            table.align.as_deref(),
+            Some(&[AlignType::Left, AlignType::Right][..])
+        );
+        let position = table.position.unwrap();
+        assert_eq!(
+            (position.start.line, position.end.line, position.end.column),
+            (2, 5, 6)
+        );
+
+        let rows: Vec<_> = table.body().collect();
+        assert_eq!(rows.len(), 2);
+        let RowContent::TableCell(cell) = &rows[0].children[0];
+        match &cell.children[..] {
+            [PhrasingContent::StaticPhrasingContent(StaticPhrasingContent::InlineCode(code))] => {
+                assert_eq!(code.value, b"a|b")
+            }
+            other => panic!("expected inline code, got {:?}", other),
+        }
+        let position = cell.position.unwrap();
+        assert_eq!((position.start.column, position.end.column), (1, 10));
+        // the missing cell is filled in
+        let RowContent::TableCell(cell) = &rows[1].children[1];
+        assert!(cell.children.is_empty() && cell.position.is_none());
+
+        let mut value = serde_json::to_value(&root).unwrap();
+        without_positions(&mut value);
+        assert_eq!(value["children"][1]["align"], json!(["left", "right"]));
+        assert_eq!(value["children"][1]["children"][0]["type"], "tableRow");
+        let json = serde_json::to_string(&root).unwrap();
+        let root = from_mdast_json(&json).unwrap();
+        assert!(matches!(
+            root.flow_content().nth(1),
+            Some(FlowContent::Table(_))
+        ));
+
+        let root = marcup::markdown::parse_with_options(markdown, &Options::commonmark()).unwrap();
+        assert_eq!(root.flow_content().count(), 1);
+    }
+
+    #[test]
+    fn parse_table_escaped_pipes() {
+        // `\\|` is an escaped backslash before a pipe that ends the cell, `\|` is a pipe in the cell
+        let markdown = "| a | b |\n| - | - |\n| x \\\\| y \\| z |\n";
+        let root = marcup::markdown::parse(markdown).unwrap();
+        let table = match root.flow_content().next() {
+            Some(FlowContent::Table(table)) => table,
+            other => panic!("expected table, got {:?}", other),
+        };
+        let row = table.body().next().unwrap();
+        let cells: Vec<_> = row
+            .children
+            .iter()
+            .map(|RowContent::TableCell(cell)| match &cell.children[..] {
+                [PhrasingContent::StaticPhrasingContent(StaticPhrasingContent::Text(text))] => {
+                    text.value.as_deref().unwrap()
+                }
+                other => panic!("expected text, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(cells, vec![&b"x \\"[..], &b"y | z"[..]]);
+    }
+
+    #[test]
+    fn parse_code_blocks() {
+        let markdown = "  ~~~~ python  linenos\n  def f():\n    pass\n ~~~\n~~~~~~\n\n    indented\n\n> ```\n> unclosed\n\nafter\n";
+        let root = marcup::markdown::parse(markdown).unwrap();
+        let code: Vec<_> = root
+            .flow_content()
+            .filter_map(|content| match content {
+                FlowContent::Code(code) => Some(code),
+                FlowContent::BlockQuote(quote) => match &quote.children[0] {
+                    FlowContent::Code(code) => Some(code),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .map(|code| {
+                (
+                    code.lang.as_deref(),
+                    code.meta.as_deref(),
+                    String::from_utf8(code.value.clone()).unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            code,
+            vec![
+                // a shorter fence does not close the block, and up to the fence's indentation is removed
+                (
+                    Some("python"),
+                    Some("linenos"),
+                    "def f():\n  pass\n~~~".to_string()
+                ),
+                (None, None, "indented".to_string()),
+                // an unclosed fence runs to the end of its container
+                (None, None, "unclosed".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_code_meta_attributes() {
+        let markdown = "```rust title=\"main.rs\" {1,3-5} showLineNumbers\nfn main() {}\n```\n\n```\nplain\n```\n\n```js title=\"a.js\n```\n";
+        let root = marcup::markdown::parse(markdown).unwrap();
+        let code: Vec<_> = root
+            .flow_content()
+            .filter_map(|content| match content {
+                FlowContent::Code(code) => Some(code),
+                _ => None,
+            })
+            .collect();
+
+        let attributes = code[0].meta_attributes().unwrap();
+        assert_eq!(
+            attributes
+                .iter()
+                .map(|attribute| attribute.kind.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                MetaAttributeKind::Pair {
+                    key: "title".to_string(),
+                    value: "main.rs".to_string()
+                },
+                MetaAttributeKind::Lines(vec![1..=1, 3..=5]),
+                MetaAttributeKind::Flag("showLineNumbers".to_string()),
+            ]
+        );
+        let meta = code[0].meta.as_deref().unwrap();
+        assert_eq!(&meta[attributes[1].span.clone()], "{1,3-5}");
+
+        assert_eq!(code[1].meta_attributes().unwrap(), vec![]);
+
+        let error = code[2].meta_attributes().unwrap_err();
+        assert_eq!(error.to_string(), "unterminated quoted value at offset 0");
+    }
+
+    #[test]
+    fn parse_code_meta_escapes() {
+        // the markdown escapes are gone from the meta, so a quote is escaped with an escaped backslash
+        let markdown = "```rust title=\"say \\\\\\\"hi\\\\\\\"\"\n```\n\n```rust title=\"say \\\"hi\\\"\"\n```\n";
+        let root = marcup::markdown::parse(markdown).unwrap();
+        let code: Vec<_> = root
+            .flow_content()
+            .filter_map(|content| match content {
+                FlowContent::Code(code) => Some(code),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(code[0].meta.as_deref(), Some("title=\"say \\\"hi\\\"\""));
+        let attributes = code[0].meta_attributes().unwrap();
+        assert_eq!(
+            attributes[0].kind,
+            MetaAttributeKind::Pair {
+                key: "title".to_string(),
+                value: "say \"hi\"".to_string()
+            }
+        );
+        let position = attributes[0].position.unwrap();
+        assert_eq!((position.start.column, position.end.column), (9, 31));
+
+        assert_eq!(code[1].meta.as_deref(), Some("title=\"say \"hi\"\""));
+        let error = code[1].meta_attributes().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "expected whitespace after attribute at offset 12"
+        );
+
+        let code = Code::new(None, Some("title=\"a\"".to_string()), Vec::new());
+        assert_eq!(code.meta_attributes().unwrap()[0].position, None);
+    }
+
+    #[test]
+    fn parse_code_meta_positions() {
+        let markdown = "> ```js title=\\\"a\\\" {1}\n> x\n> ```\n";
+        let root = marcup::markdown::parse(markdown).unwrap();
+        let code = match root.flow_content().next() {
+            Some(FlowContent::BlockQuote(quote)) => match &quote.children[0] {
+                FlowContent::Code(code) => code,
+                other => panic!("expected code, got {:?}", other),
+            },
+            other => panic!("expected a blockquote, got {:?}", other),
+        };
+        assert_eq!(code.meta.as_deref(), Some("title=\"a\" {1}"));
+
+        let attributes = code.meta_attributes().unwrap();
+        let places: Vec<_> = attributes
+            .iter()
+            .map(|attribute| {
+                let position = attribute.position.unwrap();
+                (
+                    attribute.span.clone(),
+                    (
+                        position.start.line,
+                        position.start.column,
+                        position.start.offset,
+                    ),
+                    (position.end.line, position.end.column, position.end.offset),
+                )
+            })
+            .collect();
+        assert_eq!(
+            places,
+            vec![
+                (0..9, (1, 9, Some(8)), (1, 20, Some(19))),
+                (10..13, (1, 21, Some(20)), (1, 24, Some(23)))
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_lists() {
+        let markdown = "3) three\n4) four\n\n   more\n5) five\n- tight\n  + nested\n\n  + loose\n";
+        let root = marcup::markdown::parse(markdown).unwrap();
+        let lists: Vec<&List> = root
+            .flow_content()
+            .filter_map(|content| match content {
+                FlowContent::List(list) => Some(list),
+                _ => None,
+            })
+            .collect();
+        // changing the marker type starts a new list
+        assert_eq!(lists.len(), 2);
+
+        let ordered = lists[0];
+        assert_eq!((ordered.ordered, ordered.start), (Some(true), Some(3)));
+        assert_eq!(ordered.spread, Some(false));
+        let spread: Vec<_> = ordered
+            .children
+            .iter()
+            .map(|ListContent::ListItem(item)| item.spread)
+            .collect();
+        assert_eq!(spread, vec![Some(false), Some(true), Some(false)]);
+
+        let bullet = lists[1];
+        assert_eq!((bullet.ordered, bullet.start), (Some(false), None));
+        assert_eq!(bullet.spread, Some(false));
+        let ListContent::ListItem(item) = &bullet.children[0];
+        assert_eq!(item.spread, Some(false));
+        match &item.children[1] {
+            FlowContent::List(nested) => {
+                assert_eq!(nested.spread, Some(true));
+                assert_eq!(nested.children.len(), 2);
+            }
+            other => panic!("expected nested list, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn list_tasks() {
+        let markdown = "# Checklist\n\n- [x] Write the RFD\n- [ ] Review\n  > - [X] nested\n- [ ]\n- plain [ ] item\n";
+        let root = marcup::markdown::parse(markdown).unwrap();
+        let tasks = root.tasks();
+        let checked: Vec<_> = tasks.iter().map(|task| task.checked).collect();
+        assert_eq!(checked, vec![Some(true), Some(false), Some(true)]);
+        let position = tasks[1].position.unwrap();
+        assert_eq!((position.start.line, position.end.line), (4, 5));
+
+        // the marker is not part of the text
+        match &tasks[0].children[0] {
+            FlowContent::Content(Content::Paragraph(paragraph)) => {
+                match &paragraph.children[..] {
+                    [PhrasingContent::StaticPhrasingContent(StaticPhrasingContent::Text(text))] => {
+                        assert_eq!(text.value.as_deref(), Some(&b"Write the RFD"[..]))
+                    }
+                    other => panic!("expected text, got {:?}", other),
+                }
+                let position = paragraph.position.unwrap();
+                assert_eq!((position.start.column, position.end.column), (7, 20));
+            }
+            other => panic!("expected paragraph, got {:?}", other),
+        }
+
+        let json = serde_json::to_string(&root).unwrap();
+        assert!(json.contains(r#""type":"listItem","spread":false,"checked":true"#));
+        let root = from_mdast_json(&json).unwrap();
+        assert_eq!(root.tasks().len(), 3);
+
+        let root = marcup::markdown::parse_with_options(markdown, &Options::commonmark()).unwrap();
+        assert!(root.tasks().is_empty());
+        // items that are not tasks are written with a null checked field like remark does
+        let json = serde_json::to_string(&root).unwrap();
+        assert!(json.contains(r#""type":"listItem","spread":false,"checked":null"#));
+    }
+
+    #[test]
+    fn parse_line_breaks() {
+        let markdown = "> Roses are red,  \n> violets are blue\\\n> and so\n> are you.\n";
+        let root = marcup::markdown::parse(markdown).unwrap();
+        let flow_content: Vec<&FlowContent> = root.flow_content().collect();
+        let children = match flow_content[0] {
+            FlowContent::BlockQuote(quote) => match &quote.children[0] {
+                FlowContent::Content(Content::Paragraph(paragraph)) => &paragraph.children,
+                other => panic!("expected paragraph, got {:?}", other),
+            },
+            other => panic!("expected blockquote, got {:?}", other),
+        };
+        assert_eq!(children.len(), 5);
+        assert!(matches!(
+            children[1],
+            PhrasingContent::StaticPhrasingContent(StaticPhrasingContent::Break(_))
+        ));
+        assert!(matches!(
+            children[3],
+            PhrasingContent::StaticPhrasingContent(StaticPhrasingContent::Break(_))
+        ));
+        match &children[4] {
+            PhrasingContent::StaticPhrasingContent(StaticPhrasingContent::Text(text)) => {
+                assert_eq!(text.value.as_deref(), Some(&b"and so\nare you."[..]))
+            }
+            other => panic!("expected text, got {:?}", other),
+        }
+
+        let json = serde_json::to_string(&root).unwrap();
+        assert_eq!(json.matches(r#""type":"break""#).count(), 2);
+        let root = from_mdast_json(&json).unwrap();
+        assert_eq!(serde_json::to_string(&root).unwrap(), json);
+    }
+
+    #[test]
+    fn parse_strikethrough() {
+        let markdown = "~~old~~ and ~gone~\n";
+        let root = marcup::markdown::parse(markdown).unwrap();
+        let json = serde_json::to_string(&root).unwrap();
+        assert_eq!(json.matches(r#""type":"delete""#).count(), 2);
+        assert!(from_mdast_json(&json).is_ok());
+
+        let options = Options {
+            single_tilde: false,
+            ..Options::default()
+        };
+        let root = marcup::markdown::parse_with_options(markdown, &options).unwrap();
+        let json = serde_json::to_string(&root).unwrap();
+        assert_eq!(json.matches(r#""type":"delete""#).count(), 1);
+        assert!(json.contains("~gone~"));
+
+        let root = marcup::markdown::parse_with_options(markdown, &Options::commonmark()).unwrap();
+        let json = serde_json::to_string(&root).unwrap();
+        assert!(!json.contains(r#""type":"delete""#));
+    }
+
+    #[test]
+    fn parse_escapes_and_references() {
+        let markdown = "```c&#43;&#43; title=\"a\\_b\"\nx\n```\n\nTom &amp; Jerry\\* [link](/a&amp;b \"\\\"t\\\"\")\n\n[ref]: /caf&eacute; 'it\\'s'\n";
+        let root = marcup::markdown::parse(markdown).unwrap();
+        let flow_content: Vec<&FlowContent> = root.flow_content().collect();
+        match flow_content[0] {
+            FlowContent::Code(code) => {
+                assert_eq!(code.lang.as_deref(), Some("c++"));
+                assert_eq!(code.meta.as_deref(), Some("title=\"a_b\""));
+                // the source spelling is kept next to the values
+                assert_eq!(code.raw_lang(), Some("c&#43;&#43;"));
+                assert_eq!(code.raw_meta(), Some("title=\"a\\_b\""));
+            }
+            other => panic!("expected code, got {:?}", other),
+        }
+        match flow_content[1] {
+            FlowContent::Content(Content::Paragraph(paragraph)) => match &paragraph.children[..] {
+                [PhrasingContent::StaticPhrasingContent(StaticPhrasingContent::Text(text)), PhrasingContent::Link(link)] =>
+                {
+                    assert_eq!(text.raw.as_deref(), Some("Tom &amp; Jerry\\* "));
+                    assert_eq!(link.url, "/a&b");
+                    assert_eq!(link.raw_url.as_deref(), Some("/a&amp;b"));
+                    assert_eq!(link.title.as_deref(), Some("\"t\""));
+                    assert_eq!(link.raw_title.as_deref(), Some("\\\"t\\\""));
+                }
+                other => panic!("expected text and a link, got {:?}", other),
+            },
+            other => panic!("expected paragraph, got {:?}", other),
+        }
+        let definition = root.resolve(&Association::new("ref")).unwrap();
+        assert_eq!(definition.url(), "/café");
+        assert_eq!(definition.title(), Some("it's".to_string()));
+        assert_eq!(definition.raw_url.as_deref(), Some("/caf&eacute;"));
+        assert_eq!(definition.raw_title.as_deref(), Some("it\\'s"));
+
+        // the source spelling is not part of mdast
+        let json = serde_json::to_string(&root).unwrap();
+        assert!(json.contains(r#"{"type":"text","value":"Tom & Jerry* ","position""#));
+        assert!(!json.contains("raw"));
+        let root = from_mdast_json(&json).unwrap();
+        assert_eq!(serde_json::to_string(&root).unwrap(), json);
+    }
+
+    #[test]
+    fn parse_html() {
+        let markdown = "<details>\n<summary>More</summary>\n\nHidden *text*.\n\n</details>\n\nSome <kbd>Ctrl</kbd> <!-- todo:\nexpand -->\n";
+        let root = marcup::markdown::parse(markdown).unwrap();
+        let flow_content: Vec<&FlowContent> = root.flow_content().collect();
+        assert_eq!(flow_content.len(), 4);
+        match flow_content[0] {
+            FlowContent::HTML(html) => {
+                assert_eq!(html.value, b"<details>\n<summary>More</summary>".to_vec())
+            }
+            other => panic!("expected html, got {:?}", other),
+        }
+        assert!(matches!(flow_content[2], FlowContent::HTML(_)));
+
+        let children = match flow_content[3] {
+            FlowContent::Content(Content::Paragraph(paragraph)) => &paragraph.children,
+            other => panic!("expected paragraph, got {:?}", other),
+        };
+        let html: Vec<&[u8]> = children
+            .iter()
+            .filter_map(|child| match child {
+                PhrasingContent::StaticPhrasingContent(StaticPhrasingContent::HTML(html)) => {
+                    Some(html.value.as_slice())
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            html,
+            vec![
+                &b"<kbd>"[..],
+                &b"</kbd>"[..],
+                &b"<!-- todo:\nexpand -->"[..]
+            ]
+        );
+        let json = serde_json::to_string(&root).unwrap();
+        assert!(from_mdast_json(&json).is_ok());
+    }
+
+    #[test]
+    fn parse_autolinks() {
+        let markdown =
+            "Docs at <https://example.com/docs>, www.example.com and help@example.com.\n";
+        let root = marcup::markdown::parse(markdown).unwrap();
+        let json = serde_json::to_string(&root).unwrap();
+        assert_eq!(json.matches(r#""type":"link""#).count(), 3);
+        assert!(json.contains(r#""url":"http://www.example.com""#));
+        assert!(json.contains(r#""url":"mailto:help@example.com""#));
+        assert!(from_mdast_json(&json).is_ok());
+
+        let root = marcup::markdown::parse_with_options(markdown, &Options::commonmark()).unwrap();
+        let json = serde_json::to_string(&root).unwrap();
+        assert_eq!(json.matches(r#""type":"link""#).count(), 1);
+    }
+
+    #[test]
+    fn parse_footnotes() {
+        let markdown = "Decided[^why] and noted.^[Inline.]\n\n[^why]: First paragraph.\n\n    Second paragraph.\n\n        code\n\nAfter[^gone].\n\n[^spare]: Unused.\n";
+        let root = marcup::markdown::parse(markdown).unwrap();
+        let flow_content: Vec<&FlowContent> = root.flow_content().collect();
+        assert_eq!(flow_content.len(), 4);
+        let definition = match flow_content[1] {
+            FlowContent::FootnoteDefinition(definition) => definition,
+            other => panic!("expected footnote definition, got {:?}", other),
+        };
+        assert_eq!(definition.association.identifier, "why");
+        assert_eq!(definition.children.len(), 3);
+        assert!(matches!(definition.children[2], FlowContent::Code(_)));
+        let position = definition.position.unwrap();
+        assert_eq!((position.start.line, position.end.line), (3, 7));
+
+        let report = root.footnotes();
+        assert_eq!(report.references.len(), 1);
+        assert_eq!(report.orphaned[0].association.identifier, "gone");
+        assert_eq!(report.orphaned[0].position.unwrap().start.line, 9);
+        assert_eq!(report.unused[0].association.identifier, "spare");
+        assert!(root.resolve_footnote(&Association::new("WHY")).is_some());
+
+        let json = serde_json::to_string(&root).unwrap();
+        assert!(json.contains(r#""type":"footnoteReference","identifier":"why","label":"why""#));
+        // like in GFM a reference without a definition stays text
+        assert!(!json.contains(r#""identifier":"gone""#));
+        assert!(json.contains("[^gone]"));
+        assert!(json.contains(r#""type":"footnote","children""#));
+        let root = from_mdast_json(&json).unwrap();
+        assert_eq!(root.footnotes().definitions.len(), 2);
+        let report = root.footnotes();
+        assert_eq!(report.orphaned[0].association.identifier, "gone");
+        assert_eq!(report.orphaned[0].position.unwrap().start.line, 9);
+
+        let root = marcup::markdown::parse_with_options(markdown, &Options::commonmark()).unwrap();
+        assert!(root.footnotes().references.is_empty());
+    }
+
+    #[test]
+    fn front_matter_map() {
+        let root =
+            marcup::markdown::parse("---\ntitle: Hello\ntags: [a, b]\n---\n# Body\n").unwrap();
+        let front_matter = root.front_matter().unwrap().map().unwrap();
+        assert_eq!(
+            serde_json::Value::Object(front_matter),
+            json!({"title": "Hello", "tags": ["a", "b"]})
+        );
+        assert!(matches!(
+            root.flow_content().next(),
+            Some(FlowContent::Heading(_))
+        ));
+
+        // the format does not matter
+        for markdown in &[
+            "+++\ntitle = \"Hello\"\ntags = [\"a\", \"b\"]\n+++\n# Body\n",
+            "{\n  \"title\": \"Hello\",\n  \"tags\": [\"a\", \"b\"]\n}\n# Body\n",
+        ] {
+            let root = marcup::markdown::parse(markdown).unwrap();
+            assert_eq!(
+                serde_json::Value::Object(root.front_matter().unwrap().map().unwrap()),
+                json!({"title": "Hello", "tags": ["a", "b"]})
+            );
+        }
+
+        let root = marcup::markdown::parse("---\ntitle: Hello\n  tags: [a\n---\n").unwrap();
+        let error = root.front_matter().unwrap().map().unwrap_err();
+        assert_eq!(error.line(), 3);
+    }
+
+    #[derive(serde_derive::Deserialize, Debug)]
+    struct Adr {
+        status: String,
+        deciders: Vec<String>,
+    }
+
+    #[test]
+    fn front_matter_as_struct() {
+        let root = marcup::markdown::parse(
+            "---\nstatus: accepted\ndeciders: [Sean, Tom]\n---\n# Use marcup\n",
+        )
+        .unwrap();
+        let adr: Adr = root.front_matter_as().unwrap().unwrap();
+        assert_eq!(adr.status, "accepted");
+        assert_eq!(adr.deciders, vec!["Sean", "Tom"]);
+
+        let root = marcup::markdown::parse("\n---\nstatus: accepted\n---\n").unwrap();
+        assert!(root.front_matter_as::<Adr>().unwrap().is_none());
+
+        let root = marcup::markdown::parse("+++\nstatus = \"accepted\"\n+++\n").unwrap();
+        let error = root.front_matter_as::<Adr>().unwrap_err();
+        assert_eq!(error.message(), "missing field `deciders`");
+        let position = error.position().unwrap();
+        assert_eq!((position.start.line, position.end.line), (1, 3));
+    }
+
+    #[test]
+    fn edit_front_matter() {
+        let markdown = "---\n# decision record\nstatus: proposed # or accepted\ndeciders: [Sean]\n---\n# Use marcup\n\n*  body  *\r\n";
+        let mut root = marcup::markdown::parse(markdown).unwrap();
+        let front_matter = root.front_matter_mut().unwrap();
+        front_matter.set("status", "accepted").unwrap();
+        front_matter.insert("updated", "2021-02-03").unwrap();
+        assert_eq!(
+            front_matter.replace_in(markdown),
+            "---\n# decision record\nstatus: accepted # or accepted\ndeciders: [Sean]\nupdated: 2021-02-03\n---\n# Use marcup\n\n*  body  *\r\n"
+        );
+    }
 }
 
 // ## Hello, *World*!
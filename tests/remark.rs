@@ -0,0 +1,115 @@
+use marcup::ast::from_mdast_json;
+use marcup::markdown::Options;
+use serde_json::Value;
+use std::path::Path;
+
+// Parses every markdown fixture in tests/fixtures and compares the tree with the JSON remark-parse produced
+// for it, which is checked in next to the fixture with a .json extension. Regenerate those with `node unify.js`.
+// Fixtures named gfm-*.md are parsed with the GFM extensions on both sides.
+// The remark JSON is also deserialized and serialized again which must give back the same JSON.
+// Set REMARK_IGNORE_POSITIONS to compare the trees without their positions.
+
+/// Returns the path to the first difference between `actual` and `expected`, if any.
+/// `position` fields are skipped when `ignore_positions` is set.
+fn difference(
+    actual: &Value,
+    expected: &Value,
+    ignore_positions: bool,
+    path: &str,
+) -> Option<String> {
+    match (actual, expected) {
+        (Value::Object(actual), Value::Object(expected)) => {
+            let mut keys: Vec<&String> = actual.keys().chain(expected.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            keys.into_iter()
+                .filter(|key| !(ignore_positions && *key == "position"))
+                .find_map(|key| {
+                    let path = format!("{}/{}", path, key);
+                    match (actual.get(key), expected.get(key)) {
+                        (Some(actual), Some(expected)) => {
+                            difference(actual, expected, ignore_positions, &path)
+                        }
+                        (actual, expected) => {
+                            Some(format!("{}: {:?} != {:?}", path, actual, expected))
+                        }
+                    }
+                })
+        }
+        (Value::Array(actual), Value::Array(expected)) if actual.len() == expected.len() => actual
+            .iter()
+            .zip(expected)
+            .enumerate()
+            .find_map(|(index, (actual, expected))| {
+                difference(
+                    actual,
+                    expected,
+                    ignore_positions,
+                    &format!("{}/{}", path, index),
+                )
+            }),
+        _ if actual == expected => None,
+        _ => Some(format!("{}: {} != {}", path, actual, expected)),
+    }
+}
+
+#[test]
+fn remark_fixtures() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let ignore_all_positions = std::env::var_os("REMARK_IGNORE_POSITIONS").is_some();
+
+    let mut markdown_files: Vec<_> = std::fs::read_dir(&fixtures)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension() == Some("md".as_ref()))
+        .collect();
+    markdown_files.sort();
+    assert!(
+        !markdown_files.is_empty(),
+        "no fixtures found in {:?}",
+        fixtures
+    );
+
+    let mut failures = Vec::new();
+    for markdown_file in &markdown_files {
+        let name = markdown_file.file_stem().unwrap().to_str().unwrap();
+        let markdown = std::fs::read_to_string(markdown_file).unwrap();
+        let json = std::fs::read_to_string(markdown_file.with_extension("json")).unwrap();
+        let expected: Value = serde_json::from_str(&json).unwrap();
+
+        // remark-parse does not know about front matter without a plugin, gfm- fixtures are parsed with
+        // remark-gfm which has no footnotes in the version that goes with remark-parse 9
+        let options = if name.starts_with("gfm-") {
+            Options {
+                front_matter: false,
+                footnotes: false,
+                ..Options::default()
+            }
+        } else {
+            Options::commonmark()
+        };
+        let root = marcup::markdown::parse_with_options(&markdown, &options).unwrap();
+        let actual = serde_json::to_value(&root).unwrap();
+        // the tree must also survive the equivalent of JSON.parse(JSON.stringify(tree))
+        let reparsed = from_mdast_json(&json).unwrap();
+        if let Some(difference) = difference(
+            &serde_json::to_value(&reparsed).unwrap(),
+            &expected,
+            false,
+            "",
+        ) {
+            failures.push(format!("{} (deserialized): {}", name, difference));
+        }
+        if let Some(difference) = difference(&actual, &expected, ignore_all_positions, "") {
+            failures.push(format!("{}: {}", name, difference));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} of {} fixtures differ from remark:\n{}",
+        failures.len(),
+        markdown_files.len(),
+        failures.join("\n")
+    );
+}
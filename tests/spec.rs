@@ -0,0 +1,253 @@
+mod html;
+
+use marcup::markdown::Options;
+use std::collections::BTreeMap;
+
+// Runs the examples of the vendored CommonMark spec (tests/spec/spec.txt, version 0.30) through the
+// markdown parser and compares the rendered HTML with the expected output.
+// Run with `cargo test --test spec -- --nocapture` to see the conformance report per section.
+// Set SPEC_EXAMPLES to a comma separated list of example numbers to print their markdown and output.
+
+// Minimum number of passing examples. Raise this as conformance improves so regressions are caught.
+const MINIMUM_PASSING: usize = 652;
+
+const FENCE: &str = "````````````````````````````````";
+
+struct Example {
+    number: usize,
+    section: String,
+    markdown: String,
+    html: String,
+}
+
+fn examples(spec: &str) -> Vec<Example> {
+    let mut examples = Vec::new();
+    let mut section = String::new();
+    let mut lines = spec.lines();
+    while let Some(line) = lines.next() {
+        if line.starts_with(FENCE) && line.ends_with("example") {
+            let mut markdown = String::new();
+            let mut html = String::new();
+            let mut in_html = false;
+            for line in lines.by_ref() {
+                if line == FENCE {
+                    break;
+                } else if line == "." && !in_html {
+                    in_html = true;
+                } else if in_html {
+                    html.push_str(line);
+                    html.push('\n');
+                } else {
+                    markdown.push_str(line);
+                    markdown.push('\n');
+                }
+            }
+            examples.push(Example {
+                number: examples.len() + 1,
+                section: section.clone(),
+                markdown: markdown.replace('→', "\t"),
+                html: html.replace('→', "\t"),
+            });
+        } else if let Some(heading) = line.strip_prefix("## ").or_else(|| line.strip_prefix("# ")) {
+            section = heading.to_string();
+        }
+    }
+    examples
+}
+
+#[test]
+fn commonmark_spec() {
+    let spec = include_str!("spec/spec.txt");
+    let examples = examples(spec);
+    assert_eq!(examples.len(), 652);
+
+    // section name -> (passed, total), in order of first appearance
+    let mut order: Vec<String> = Vec::new();
+    let mut results: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    let mut failed = Vec::new();
+    let verbose: Vec<usize> = std::env::var("SPEC_EXAMPLES")
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|n| n.trim().parse().ok())
+        .collect();
+    for example in &examples {
+        let rendered =
+            marcup::markdown::parse_with_options(&example.markdown, &Options::commonmark())
+                .map(|root| html::render(&root));
+        let passed = rendered.as_ref() == Ok(&example.html);
+        if verbose.contains(&example.number) {
+            println!(
+                "example {} ({})\n--- markdown\n{}--- expected\n{}--- actual\n{}",
+                example.number,
+                if passed { "passed" } else { "failed" },
+                example.markdown,
+                example.html,
+                match &rendered {
+                    Ok(html) => html.clone(),
+                    Err(err) => format!("{}\n", err),
+                }
+            );
+        }
+        if !results.contains_key(&example.section) {
+            order.push(example.section.clone());
+        }
+        let entry = results.entry(example.section.clone()).or_insert((0, 0));
+        entry.1 += 1;
+        if passed {
+            entry.0 += 1;
+        } else {
+            failed.push(example.number);
+        }
+    }
+
+    let passed = examples.len() - failed.len();
+    println!("{:<45} {:>7}", "section", "passed");
+    for section in &order {
+        let (section_passed, section_total) = results[section];
+        println!("{:<45} {:>3}/{:<3}", section, section_passed, section_total);
+    }
+    println!(
+        "{:<45} {:>3}/{:<3} ({:.1}%)",
+        "total",
+        passed,
+        examples.len(),
+        100.0 * passed as f64 / examples.len() as f64
+    );
+    println!("failed examples: {:?}", failed);
+
+    assert!(
+        passed >= MINIMUM_PASSING,
+        "only {} of {} spec examples passed, expected at least {}",
+        passed,
+        examples.len(),
+        MINIMUM_PASSING
+    );
+}
+
+// Examples of the GFM spec (0.29-gfm) for the extensions it adds to CommonMark, by example number.
+// They are parsed with the default options, which enable the extensions.
+const GFM_EXAMPLES: &[(usize, &str, &str)] = &[
+    (
+        198,
+        "| foo | bar |\n| --- | --- |\n| baz | bim |\n",
+        "<table>\n<thead>\n<tr>\n<th>foo</th>\n<th>bar</th>\n</tr>\n</thead>\n<tbody>\n<tr>\n<td>baz</td>\n<td>bim</td>\n</tr>\n</tbody>\n</table>\n",
+    ),
+    (
+        199,
+        "| abc | defghi |\n:-: | -----------:\nbar | baz\n",
+        "<table>\n<thead>\n<tr>\n<th align=\"center\">abc</th>\n<th align=\"right\">defghi</th>\n</tr>\n</thead>\n<tbody>\n<tr>\n<td align=\"center\">bar</td>\n<td align=\"right\">baz</td>\n</tr>\n</tbody>\n</table>\n",
+    ),
+    (
+        200,
+        "| f\\|oo  |\n| ------ |\n| b `\\|` az |\n| b **\\|** im |\n",
+        "<table>\n<thead>\n<tr>\n<th>f|oo</th>\n</tr>\n</thead>\n<tbody>\n<tr>\n<td>b <code>|</code> az</td>\n</tr>\n<tr>\n<td>b <strong>|</strong> im</td>\n</tr>\n</tbody>\n</table>\n",
+    ),
+    (
+        201,
+        "| abc | def |\n| --- | --- |\n| bar | baz |\n> bar\n",
+        "<table>\n<thead>\n<tr>\n<th>abc</th>\n<th>def</th>\n</tr>\n</thead>\n<tbody>\n<tr>\n<td>bar</td>\n<td>baz</td>\n</tr>\n</tbody>\n</table>\n<blockquote>\n<p>bar</p>\n</blockquote>\n",
+    ),
+    (
+        202,
+        "| abc | def |\n| --- | --- |\n| bar | baz |\nbar\n\nbar\n",
+        "<table>\n<thead>\n<tr>\n<th>abc</th>\n<th>def</th>\n</tr>\n</thead>\n<tbody>\n<tr>\n<td>bar</td>\n<td>baz</td>\n</tr>\n<tr>\n<td>bar</td>\n<td></td>\n</tr>\n</tbody>\n</table>\n<p>bar</p>\n",
+    ),
+    (
+        203,
+        "| abc | def |\n| --- |\n| bar |\n",
+        "<p>| abc | def |\n| --- |\n| bar |</p>\n",
+    ),
+    (
+        204,
+        "| abc | def |\n| --- | --- |\n| bar |\n| bar | baz | boo |\n",
+        "<table>\n<thead>\n<tr>\n<th>abc</th>\n<th>def</th>\n</tr>\n</thead>\n<tbody>\n<tr>\n<td>bar</td>\n<td></td>\n</tr>\n<tr>\n<td>bar</td>\n<td>baz</td>\n</tr>\n</tbody>\n</table>\n",
+    ),
+    (
+        205,
+        "| abc | def |\n| --- | --- |\n",
+        "<table>\n<thead>\n<tr>\n<th>abc</th>\n<th>def</th>\n</tr>\n</thead>\n</table>\n",
+    ),
+    (
+        279,
+        "- [ ] foo\n- [x] bar\n",
+        "<ul>\n<li><input disabled=\"\" type=\"checkbox\"> foo</li>\n<li><input checked=\"\" disabled=\"\" type=\"checkbox\"> bar</li>\n</ul>\n",
+    ),
+    (
+        280,
+        "- [x] foo\n  - [ ] bar\n  - [x] baz\n- [ ] bim\n",
+        "<ul>\n<li><input checked=\"\" disabled=\"\" type=\"checkbox\"> foo\n<ul>\n<li><input disabled=\"\" type=\"checkbox\"> bar</li>\n<li><input checked=\"\" disabled=\"\" type=\"checkbox\"> baz</li>\n</ul>\n</li>\n<li><input disabled=\"\" type=\"checkbox\"> bim</li>\n</ul>\n",
+    ),
+    (
+        491,
+        "~~Hi~~ Hello, ~there~ world!\n",
+        "<p><del>Hi</del> Hello, <del>there</del> world!</p>\n",
+    ),
+    (
+        492,
+        "This ~~has a\n\nnew paragraph~~.\n",
+        "<p>This ~~has a</p>\n<p>new paragraph~~.</p>\n",
+    ),
+    (
+        621,
+        "www.commonmark.org\n",
+        "<p><a href=\"http://www.commonmark.org\">www.commonmark.org</a></p>\n",
+    ),
+    (
+        622,
+        "Visit www.commonmark.org/help for more information.\n",
+        "<p>Visit <a href=\"http://www.commonmark.org/help\">www.commonmark.org/help</a> for more information.</p>\n",
+    ),
+    (
+        623,
+        "Visit www.commonmark.org.\n\nVisit www.commonmark.org/a.b.\n",
+        "<p>Visit <a href=\"http://www.commonmark.org\">www.commonmark.org</a>.</p>\n<p>Visit <a href=\"http://www.commonmark.org/a.b\">www.commonmark.org/a.b</a>.</p>\n",
+    ),
+    (
+        624,
+        "www.google.com/search?q=Markup+(business)\n\nwww.google.com/search?q=Markup+(business)))\n\n(www.google.com/search?q=Markup+(business))\n\n(www.google.com/search?q=Markup+(business)\n",
+        "<p><a href=\"http://www.google.com/search?q=Markup+(business)\">www.google.com/search?q=Markup+(business)</a></p>\n<p><a href=\"http://www.google.com/search?q=Markup+(business)\">www.google.com/search?q=Markup+(business)</a>))</p>\n<p>(<a href=\"http://www.google.com/search?q=Markup+(business)\">www.google.com/search?q=Markup+(business)</a>)</p>\n<p>(<a href=\"http://www.google.com/search?q=Markup+(business)\">www.google.com/search?q=Markup+(business)</a></p>\n",
+    ),
+    (
+        625,
+        "www.google.com/search?q=(business))+ok\n",
+        "<p><a href=\"http://www.google.com/search?q=(business))+ok\">www.google.com/search?q=(business))+ok</a></p>\n",
+    ),
+    (
+        626,
+        "www.google.com/search?q=commonmark&hl=en\n\nwww.google.com/search?q=commonmark&hl;\n",
+        "<p><a href=\"http://www.google.com/search?q=commonmark&amp;hl=en\">www.google.com/search?q=commonmark&amp;hl=en</a></p>\n<p><a href=\"http://www.google.com/search?q=commonmark\">www.google.com/search?q=commonmark</a>&amp;hl;</p>\n",
+    ),
+    (
+        627,
+        "www.commonmark.org/he<lp\n",
+        "<p><a href=\"http://www.commonmark.org/he\">www.commonmark.org/he</a>&lt;lp</p>\n",
+    ),
+    (
+        628,
+        "http://commonmark.org\n\n(Visit https://encrypted.google.com/search?q=Markup+(business))\n\nAnonymous FTP is available at ftp://foo.bar.baz.\n",
+        "<p><a href=\"http://commonmark.org\">http://commonmark.org</a></p>\n<p>(Visit <a href=\"https://encrypted.google.com/search?q=Markup+(business)\">https://encrypted.google.com/search?q=Markup+(business)</a>)</p>\n<p>Anonymous FTP is available at ftp://foo.bar.baz.</p>\n",
+    ),
+    (
+        629,
+        "foo@bar.baz\n",
+        "<p><a href=\"mailto:foo@bar.baz\">foo@bar.baz</a></p>\n",
+    ),
+    (
+        630,
+        "hello@mail+xyz.example isn't valid, but hello+xyz@mail.example is.\n",
+        "<p>hello@mail+xyz.example isn't valid, but <a href=\"mailto:hello+xyz@mail.example\">hello+xyz@mail.example</a> is.</p>\n",
+    ),
+    (
+        631,
+        "a.b-c_d@a.b\n\na.b-c_d@a.b.\n\na.b-c_d@a.b-\n\na.b-c_d@a.b_\n",
+        "<p><a href=\"mailto:a.b-c_d@a.b\">a.b-c_d@a.b</a></p>\n<p><a href=\"mailto:a.b-c_d@a.b\">a.b-c_d@a.b</a>.</p>\n<p>a.b-c_d@a.b-</p>\n<p>a.b-c_d@a.b_</p>\n",
+    ),
+];
+
+#[test]
+fn gfm_spec() {
+    for (number, markdown, expected) in GFM_EXAMPLES {
+        let root = marcup::markdown::parse(markdown).unwrap();
+        assert_eq!(html::render(&root), *expected, "example {}", number);
+    }
+}
@@ -1,6 +1,19 @@
+// Regenerates the remark output for every markdown fixture in tests/fixtures, run with `node unify.js`
+var fs = require('fs')
+var path = require('path')
 var unified = require('unified')
 var markdown = require('remark-parse')
+var gfm = require('remark-gfm')
 
-var tree = unified().use(markdown).parse('# Hello world!')
+var fixtures = path.join(__dirname, 'tests', 'fixtures')
 
-console.log(tree)
\ No newline at end of file
+fs.readdirSync(fixtures)
+  .filter(function (file) { return path.extname(file) === '.md' })
+  .forEach(function (file) {
+    var processor = unified().use(markdown)
+    // gfm- fixtures also use the GFM extensions
+    if (file.indexOf('gfm-') === 0) processor = processor.use(gfm)
+    var tree = processor.parse(fs.readFileSync(path.join(fixtures, file), 'utf8'))
+    var output = path.join(fixtures, path.basename(file, '.md') + '.json')
+    fs.writeFileSync(output, JSON.stringify(tree, null, 2))
+  })